@@ -1,11 +1,12 @@
 use std::{
     fmt,
     hash::Hash,
+    iter::Sum,
     mem::MaybeUninit,
     ops::{Deref, DerefMut},
 };
 
-use super::{tiny_vec, TinyVec};
+use super::{tiny_vec, DisplaySlice, TinyVec};
 
 /// A contiguous array of elements. Similar to [`Vec<T>`], but can store up to a constant `N`
 /// amount of elements inline before spilling over and allocating on the heap.
@@ -122,6 +123,30 @@ impl<const N: usize, T> CompactVec<N, T> {
         self
     }
 
+    /// Returns a raw pointer to this `CompactVec`'s contiguous storage, which is the inline array
+    /// if not spilled or the internal `Vec<T>`'s buffer if spilled.
+    ///
+    /// The pointer is only valid until this `CompactVec` is next mutated, as that may spill it or
+    /// reallocate its buffer.
+    pub fn as_ptr(&self) -> *const T {
+        match self {
+            Self::Inlined(tiny_vec) => tiny_vec.as_slice().as_ptr(),
+            Self::Spilled(vec) => vec.as_ptr(),
+        }
+    }
+
+    /// Returns a raw mutable pointer to this `CompactVec`'s contiguous storage, which is the
+    /// inline array if not spilled or the internal `Vec<T>`'s buffer if spilled.
+    ///
+    /// The pointer is only valid until this `CompactVec` is next mutated, as that may spill it or
+    /// reallocate its buffer.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        match self {
+            Self::Inlined(tiny_vec) => tiny_vec.as_mut_slice().as_mut_ptr(),
+            Self::Spilled(vec) => vec.as_mut_ptr(),
+        }
+    }
+
     /// If spilled, returns `Some` with a reference to this `CompactVec`'s internal `Vec<T>`
     /// instance. Otherwise returns `None`.
     pub fn get_vec_if_spilled(&self) -> Option<&Vec<T>> {
@@ -272,6 +297,12 @@ impl<const N: usize, T> CompactVec<N, T> {
         }
     }
 
+    /// Replaces the element at position `index` within the `CompactVec` with `value`, returning the
+    /// old element, or `None` if `index` is out of bounds, in which case `value` is dropped.
+    pub fn set(&mut self, index: usize, value: T) -> Option<T> {
+        self.get_mut(index).map(|element| std::mem::replace(element, value))
+    }
+
     /// Clears this `CompactVec`, removing all values.
     pub fn clear(&mut self) {
         match self {
@@ -290,10 +321,82 @@ impl<const N: usize, T> CompactVec<N, T> {
             _ => {}
         }
     }
+
+    /// Retains only the elements specified by the predicate, dropping the rest. The order of the
+    /// retained elements is preserved.
+    ///
+    /// Unlike [`Vec::retain`], if `f` panics while the vector is inlined, the vector is left empty
+    /// and all of its remaining elements are leaked.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, f: F) {
+        match self {
+            Self::Inlined(tiny_vec) => tiny_vec.retain(f),
+            Self::Spilled(vec) => vec.retain(f),
+        }
+    }
+}
+
+impl<const N: usize, T: PartialEq> CompactVec<N, T> {
+    /// Removes all the elements equal to `value`, preserving the order of the remaining elements.
+    /// Returns the amount of elements removed.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let len = self.len();
+        self.retain(|element| element != value);
+        len - self.len()
+    }
+}
+
+impl<const N: usize, T> CompactVec<N, T>
+where
+    T: for<'a> Sum<&'a T>,
+{
+    /// Returns the sum of all the elements in this `CompactVec`, which is zero if it's empty.
+    pub fn sum(&self) -> T {
+        self.iter().sum()
+    }
+}
+
+impl<const N: usize, T: Ord> CompactVec<N, T> {
+    /// Sorts this `CompactVec` and removes all duplicate elements (not just consecutive ones),
+    /// returning it as a sorted set of unique elements.
+    ///
+    /// This does not un-spill the vector, even if the unique elements would fit inline.
+    pub fn into_unique_sorted(self) -> Self {
+        match self {
+            Self::Inlined(tiny_vec) => Self::Inlined(tiny_vec.into_unique_sorted()),
+            Self::Spilled(mut vec) => {
+                vec.sort_unstable();
+                vec.dedup();
+                Self::Spilled(vec)
+            }
+        }
+    }
+
+    /// Returns a reference to the smallest element in this `CompactVec`, or `None` if it's empty.
+    pub fn min_element(&self) -> Option<&T> {
+        self.iter().min()
+    }
+
+    /// Returns a reference to the largest element in this `CompactVec`, or `None` if it's empty.
+    pub fn max_element(&self) -> Option<&T> {
+        self.iter().max()
+    }
+}
+
+impl<const N: usize, T: fmt::Display> CompactVec<N, T> {
+    /// Returns a [`DisplaySlice`] that displays the elements of this `CompactVec` joined by the given
+    /// separator.
+    pub fn fmt_joined<'a>(&'a self, separator: &'a str) -> DisplaySlice<'a, T> {
+        DisplaySlice::new(self, separator)
+    }
 }
 
 impl<const N: usize, T> Extend<T> for CompactVec<N, T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = match self {
+            Self::Inlined(tiny_vec) => tiny_vec.extend_returning(iter),
+            Self::Spilled(_) => iter.into_iter(),
+        };
+
         for ele in iter {
             self.push(ele);
         }
@@ -346,6 +449,8 @@ impl<const N: usize, T> Iterator for IntoIter<N, T> {
 
 #[cfg(test)]
 mod tests {
+    use std::net::SocketAddr;
+
     use super::CompactVec;
 
     #[test]
@@ -448,4 +553,120 @@ mod tests {
 
         assert_eq!(vec.into_iter().collect::<Vec<_>>(), vec!['c', 'd']);
     }
+
+    #[test]
+    fn test_retain() {
+        let mut vec = CompactVec::<3, i32>::new();
+        vec.extend([1, 2, 3]);
+
+        vec.retain(|x| *x != 2);
+        assert!(!vec.is_spilled());
+        assert_eq!(vec.as_slice(), &[1, 3]);
+
+        vec.extend([4, 5, 6]);
+        assert!(vec.is_spilled());
+
+        vec.retain(|x| x % 2 == 1);
+        assert!(vec.is_spilled());
+        assert_eq!(vec.as_slice(), &[1, 3, 5]);
+
+        vec.retain(|_| true);
+        assert_eq!(vec.as_slice(), &[1, 3, 5]);
+    }
+
+    #[test]
+    fn test_as_ptr() {
+        let mut vec = CompactVec::<3, i32>::new();
+        vec.extend([1, 2]);
+        assert!(!vec.is_spilled());
+        assert_eq!(vec.as_ptr(), &vec[0] as *const i32);
+        assert_eq!(unsafe { *vec.as_ptr() }, 1);
+
+        unsafe { *vec.as_mut_ptr() = 10 };
+        assert_eq!(vec.as_slice(), &[10, 2]);
+
+        vec.extend([3, 4]);
+        assert!(vec.is_spilled());
+        assert_eq!(vec.as_ptr(), vec.get_vec_if_spilled().unwrap().as_ptr());
+        assert_eq!(vec.as_ptr(), &vec[0] as *const i32);
+        assert_eq!(unsafe { *vec.as_ptr() }, 10);
+
+        unsafe { *vec.as_mut_ptr().add(3) = 40 };
+        assert_eq!(vec.as_slice(), &[10, 2, 3, 40]);
+    }
+
+    #[test]
+    fn test_fmt_joined() {
+        let mut vec = CompactVec::<4, SocketAddr>::new();
+        assert_eq!(vec.fmt_joined(", ").to_string(), "");
+
+        vec.push(SocketAddr::from(([127, 0, 0, 1], 5995)));
+        assert_eq!(vec.fmt_joined(", ").to_string(), "127.0.0.1:5995");
+
+        vec.push(SocketAddr::from(([0u16, 0, 0, 0, 0, 0, 0, 1], 8080)));
+        vec.push(SocketAddr::from(([10, 0, 0, 2], 443)));
+        assert_eq!(vec.fmt_joined(", ").to_string(), "127.0.0.1:5995, [::1]:8080, 10.0.0.2:443");
+        assert_eq!(vec.fmt_joined(";").to_string(), "127.0.0.1:5995;[::1]:8080;10.0.0.2:443");
+    }
+
+    #[test]
+    fn test_into_unique_sorted() {
+        let vec: CompactVec<4, i32> = [3, 1, 3, 1].into_iter().collect();
+        assert!(!vec.is_spilled());
+        let vec = vec.into_unique_sorted();
+        assert!(!vec.is_spilled());
+        assert_eq!(vec.as_slice(), &[1, 3]);
+
+        let vec: CompactVec<4, i32> = [5, 3, 5, 1, 3, 3, 9, 1, 5, 0, 9, 3].into_iter().collect();
+        assert!(vec.is_spilled());
+        let vec = vec.into_unique_sorted();
+        assert_eq!(vec.as_slice(), &[0, 1, 3, 5, 9]);
+    }
+
+    #[test]
+    fn test_sum_min_max() {
+        let vec: CompactVec<4, i32> = [4, -2, 9].into_iter().collect();
+        assert!(!vec.is_spilled());
+        assert_eq!(vec.sum(), 11);
+        assert_eq!(vec.min_element(), Some(&-2));
+        assert_eq!(vec.max_element(), Some(&9));
+
+        let vec: CompactVec<4, i32> = [4, -2, 9, 0, 7, -5].into_iter().collect();
+        assert!(vec.is_spilled());
+        assert_eq!(vec.sum(), 13);
+        assert_eq!(vec.min_element(), Some(&-5));
+        assert_eq!(vec.max_element(), Some(&9));
+
+        let vec = CompactVec::<4, u64>::new();
+        assert_eq!(vec.sum(), 0);
+        assert_eq!(vec.min_element(), None);
+        assert_eq!(vec.max_element(), None);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut vec: CompactVec<4, i32> = [1, 2, 3].into_iter().collect();
+        assert_eq!(vec.set(0, 10), Some(1));
+        assert_eq!(vec.set(3, 40), None);
+        assert_eq!(vec.as_slice(), &[10, 2, 3]);
+
+        let mut vec: CompactVec<4, i32> = [1, 2, 3, 4, 5, 6].into_iter().collect();
+        assert!(vec.is_spilled());
+        assert_eq!(vec.set(5, 60), Some(6));
+        assert_eq!(vec.set(6, 70), None);
+        assert_eq!(vec.as_slice(), &[1, 2, 3, 4, 5, 60]);
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let mut vec: CompactVec<4, i32> = [3, 1, 3].into_iter().collect();
+        assert_eq!(vec.remove_all(&3), 2);
+        assert_eq!(vec.as_slice(), &[1]);
+
+        let mut vec: CompactVec<4, i32> = [3, 1, 3, 2, 3, 4, 1, 3].into_iter().collect();
+        assert!(vec.is_spilled());
+        assert_eq!(vec.remove_all(&3), 4);
+        assert_eq!(vec.remove_all(&7), 0);
+        assert_eq!(vec.as_slice(), &[1, 2, 4, 1]);
+    }
 }
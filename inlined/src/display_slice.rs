@@ -0,0 +1,41 @@
+//! A wrapper for displaying the elements of a slice joined by a separator.
+
+use core::fmt;
+
+/// Displays the elements of a slice with their [`Display`](fmt::Display) implementation, joined
+/// by a separator. This is returned by the `fmt_joined` methods on the inlined vector types.
+///
+/// ```
+/// use inlined::TinyVec;
+///
+/// let mut vec = TinyVec::<4, u16>::new();
+/// vec.extend_from_slice(&[80, 443, 8080]);
+/// assert_eq!(vec.fmt_joined(", ").to_string(), "80, 443, 8080");
+/// ```
+#[derive(Clone, Copy)]
+pub struct DisplaySlice<'a, T> {
+    slice: &'a [T],
+    separator: &'a str,
+}
+
+impl<'a, T> DisplaySlice<'a, T> {
+    /// Constructs a `DisplaySlice` over the given slice and separator.
+    pub const fn new(slice: &'a [T], separator: &'a str) -> Self {
+        Self { slice, separator }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for DisplaySlice<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut iter = self.slice.iter();
+        if let Some(first) = iter.next() {
+            first.fmt(f)?;
+            for element in iter {
+                f.write_str(self.separator)?;
+                element.fmt(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
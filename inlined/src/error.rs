@@ -0,0 +1,33 @@
+//! Error types shared across the inlined collections.
+
+use core::fmt;
+
+/// An error indicating an operation failed because an inlined collection didn't have enough
+/// remaining capacity. When this error is returned, the collection was left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError {
+    /// The amount of capacity the operation needed.
+    pub requested: usize,
+
+    /// The amount of capacity that was remaining in the collection.
+    pub remaining: usize,
+}
+
+impl CapacityError {
+    /// Creates a new [`CapacityError`] with the given requested and remaining capacities.
+    pub const fn new(requested: usize, remaining: usize) -> Self {
+        Self { requested, remaining }
+    }
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Insufficient capacity: requested {} but only {} remaining",
+            self.requested, self.remaining
+        )
+    }
+}
+
+impl std::error::Error for CapacityError {}
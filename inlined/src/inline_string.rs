@@ -1,7 +1,10 @@
 use core::fmt;
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
 
-use super::InlineVec;
+use super::{CapacityError, InlineVec};
 
 /// A UTF-8–encoded, inline string. Similar to [`String`], but stores chars inline instead of
 /// allocating on the heap.
@@ -10,7 +13,7 @@ use super::InlineVec;
 /// or empty will always occupy as much memory as if it were full. The upside to this is that this
 /// memory is stored inline, so operations where a small string is needed can be optimized with
 /// this type to make use of the stack, avoiding memory allocations and improving cache hits.
-#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct InlineString<const N: usize> {
     inner: InlineVec<N, u8>,
 }
@@ -29,6 +32,14 @@ impl<const N: usize> DerefMut for InlineString<N> {
     }
 }
 
+impl<const N: usize> Clone for InlineString<N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone_copy(),
+        }
+    }
+}
+
 impl<const N: usize> fmt::Debug for InlineString<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self.deref(), f)
@@ -90,6 +101,20 @@ impl<const N: usize> InlineString<N> {
         byte_count
     }
 
+    /// Appends a given string slice onto the end of this `InlineString` only if it fits entirely.
+    ///
+    /// Unlike [`push_str`](Self::push_str), this never appends partial content. If the string
+    /// doesn't fit, then this `InlineString` is left unchanged and a [`CapacityError`] is returned.
+    pub fn push_str_all(&mut self, string: &str) -> Result<(), CapacityError> {
+        let remaining_capacity = self.capacity() - self.len();
+        if string.len() > remaining_capacity {
+            return Err(CapacityError::new(string.len(), remaining_capacity));
+        }
+
+        self.inner.extend_from_slice_copied(string.as_bytes());
+        Ok(())
+    }
+
     /// Appends the given [`char`] to the end of this `InlineString`, returning how many bytes were
     /// appended.
     ///
@@ -139,6 +164,26 @@ impl<const N: usize> InlineString<N> {
         }
     }
 
+    /// Normalizes a user-entered code in this `InlineString`, such as one pasted with surrounding
+    /// whitespace or wrapped across lines, by removing all whitespace from it in place. The case is
+    /// left untouched, since codes may be case-sensitive.
+    pub fn normalize_code(&mut self) {
+        let mut new_len = 0;
+        let mut index = 0;
+
+        while let Some(c) = self.as_str()[index..].chars().next() {
+            let char_len = c.len_utf8();
+            if !c.is_whitespace() {
+                self.inner.copy_within(index..(index + char_len), new_len);
+                new_len += char_len;
+            }
+
+            index += char_len;
+        }
+
+        unsafe { self.inner.set_len(new_len) };
+    }
+
     /// Returns a mutable reference to this [`InlineString`]'s internal [`InlineVec`].
     ///
     /// # Safety
@@ -172,11 +217,24 @@ impl<const N: usize> From<&str> for InlineString<N> {
     }
 }
 
+/// Parses an `InlineString` from a string slice. Unlike [`From<&str>`], which truncates the string
+/// if it doesn't fit, this returns a [`CapacityError`] instead.
+impl<const N: usize> FromStr for InlineString<N> {
+    type Err = CapacityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = InlineString::new();
+        result.push_str_all(s)?;
+        Ok(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fmt::Write, ops::Deref};
 
     use super::InlineString;
+    use crate::CapacityError;
 
     #[test]
     fn test_push_pop() {
@@ -343,6 +401,26 @@ mod tests {
         InlineString::<8>::from("ü").truncate(1);
     }
 
+    #[test]
+    fn test_normalize_code() {
+        let mut s = InlineString::<32>::from("  \tAbC-123\n-xyZ \r\n");
+        s.normalize_code();
+        assert_eq!(s.deref(), "AbC-123-xyZ");
+
+        let mut s = InlineString::<32>::from("already-Normal");
+        s.normalize_code();
+        assert_eq!(s.deref(), "already-Normal");
+
+        let mut s = InlineString::<32>::from(" ñan\u{a0}dú\u{3000}");
+        s.normalize_code();
+        assert_eq!(s.deref(), "ñandú");
+
+        let mut s = InlineString::<32>::from(" \n\t ");
+        s.normalize_code();
+        assert_eq!(s.deref(), "");
+        assert!(s.is_empty());
+    }
+
     #[test]
     fn test_write() {
         let mut s = InlineString::<10>::new();
@@ -362,4 +440,37 @@ mod tests {
         assert_eq!(write!(s, "{}", 123), Ok(()));
         assert_eq!(s.deref(), "Goodbye: 1");
     }
+
+    #[test]
+    fn test_push_str_all() {
+        let mut s = InlineString::<8>::new();
+
+        assert_eq!(s.push_str_all("Hello"), Ok(()));
+        assert_eq!(s.deref(), "Hello");
+
+        assert_eq!(s.push_str_all(", world"), Err(CapacityError::new(7, 3)));
+        assert_eq!(s.deref(), "Hello");
+
+        assert_eq!(s.push_str_all("áé"), Err(CapacityError::new(4, 3)));
+        assert_eq!(s.deref(), "Hello");
+
+        assert_eq!(s.push_str_all("!á"), Ok(()));
+        assert_eq!(s.deref(), "Hello!á");
+
+        assert_eq!(s.push_str_all(""), Ok(()));
+        assert_eq!(s.push_str_all("?"), Err(CapacityError::new(1, 0)));
+        assert_eq!(s.deref(), "Hello!á");
+    }
+
+    #[test]
+    fn test_from_str() {
+        let s: InlineString<8> = "Hello".parse().unwrap();
+        assert_eq!(s.deref(), "Hello");
+
+        let s: InlineString<8> = "12345678".parse().unwrap();
+        assert_eq!(s.deref(), "12345678");
+
+        assert_eq!("123456789".parse::<InlineString<8>>(), Err(CapacityError::new(9, 8)));
+        assert_eq!("1234567á".parse::<InlineString<8>>(), Err(CapacityError::new(9, 8)));
+    }
 }
@@ -2,10 +2,13 @@ use std::{
     fmt,
     hash::Hash,
     io,
+    iter::Sum,
     mem::MaybeUninit,
     ops::{Deref, DerefMut},
 };
 
+use super::DisplaySlice;
+
 /// A contiguous array of elements. Similar to [`Vec<T>`], but stores elements inline instead of
 /// allocating on the heap.
 ///
@@ -115,6 +118,15 @@ impl<const N: usize, T> InlineVec<N, T> {
         self
     }
 
+    /// Returns a reference to the elements of this `InlineVec` as an array if it's full (its length is
+    /// `N`), or [`None`] otherwise.
+    pub fn as_array(&self) -> Option<&[T; N]> {
+        match self.len == N {
+            true => Some(unsafe { &*self.inner.as_ptr().cast::<[T; N]>() }),
+            false => None,
+        }
+    }
+
     /// Appends an element at the end of this `InlineVec`.
     ///
     /// Returns [`None`] if the element was appended, or [`Some`] with the passed element if the
@@ -227,6 +239,12 @@ impl<const N: usize, T> InlineVec<N, T> {
         }
     }
 
+    /// Replaces the element at position `index` within the `InlineVec` with `value`, returning the
+    /// old element, or `None` if `index` is out of bounds, in which case `value` is dropped.
+    pub fn set(&mut self, index: usize, value: T) -> Option<T> {
+        self.get_mut(index).map(|element| std::mem::replace(element, value))
+    }
+
     /// Clears this `InlineVec`, removing all values.
     pub fn clear(&mut self) {
         for i in 0..self.len {
@@ -249,6 +267,51 @@ impl<const N: usize, T> InlineVec<N, T> {
         }
     }
 
+    /// Retains only the elements specified by the predicate, dropping the rest. The order of the
+    /// retained elements is preserved.
+    ///
+    /// Elements before the first removed one are not moved.
+    ///
+    /// Unlike [`Vec::retain`], if `f` panics the vector is left empty and all of its remaining
+    /// elements are leaked.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let original_len = self.len;
+        self.len = 0;
+
+        let mut processed = 0;
+        let mut deleted = 0;
+
+        // Fast path: skip over the prefix of retained elements, which don't need to be moved.
+        while processed < original_len {
+            let element = unsafe { self.inner.get_unchecked_mut(processed) };
+            processed += 1;
+
+            if !f(unsafe { element.assume_init_ref() }) {
+                unsafe { element.assume_init_drop() };
+                deleted += 1;
+                break;
+            }
+        }
+
+        // Slow path: shift each retained element back over the gap left by the removed ones.
+        while processed < original_len {
+            let ptr = self.inner.as_mut_ptr();
+            let element = unsafe { &mut *ptr.add(processed) };
+
+            if f(unsafe { element.assume_init_ref() }) {
+                let (src, dst) = unsafe { (ptr.add(processed), ptr.add(processed - deleted)) };
+                unsafe { std::ptr::copy_nonoverlapping(src, dst, 1) };
+            } else {
+                unsafe { element.assume_init_drop() };
+                deleted += 1;
+            }
+
+            processed += 1;
+        }
+
+        self.len = original_len - deleted;
+    }
+
     /// Gets a mutable reference to this `InlineVec`'s internal storage, which may be partly
     /// uninitialized. This operation is unsafe, and the caller is responsible for ensuring this
     /// type's invariants are maintaned.
@@ -285,6 +348,25 @@ impl<const N: usize, T> Extend<T> for InlineVec<N, T> {
     }
 }
 
+impl<const N: usize, T> InlineVec<N, T> {
+    /// Appends elements from the iterator until this `InlineVec` is full, then returns the iterator
+    /// with the elements that didn't fit. No elements are taken from the iterator past the ones
+    /// that fit.
+    pub fn extend_returning<I: IntoIterator<Item = T>>(&mut self, iter: I) -> I::IntoIter {
+        let mut iter = iter.into_iter();
+        while self.len < N {
+            match iter.next() {
+                Some(ele) => {
+                    let _ = self.push(ele);
+                }
+                None => break,
+            }
+        }
+
+        iter
+    }
+}
+
 impl<const N: usize, T: Clone> InlineVec<N, T> {
     /// Clones and appends as many elements as possible from the slice to the `Vec`. Returns the
     /// amount of appended elements.
@@ -321,6 +403,93 @@ impl<const N: usize, T: Copy> InlineVec<N, T> {
 
         count
     }
+
+    /// Clones this `InlineVec` by bulk-copying its elements, instead of cloning them one by one like
+    /// [`Clone::clone`] does. This is used by the inlined string types, whose `Clone` is often on
+    /// hot paths.
+    pub fn clone_copy(&self) -> Self {
+        let mut inner: [MaybeUninit<T>; N] = unsafe { MaybeUninit::uninit().assume_init() };
+        let len = self.len;
+        unsafe { std::ptr::copy_nonoverlapping(self.inner.as_ptr(), inner.as_mut_ptr(), len) };
+
+        Self { inner, len: self.len }
+    }
+}
+
+impl<const N: usize, T: fmt::Display> InlineVec<N, T> {
+    /// Returns a [`DisplaySlice`] that displays the elements of this `InlineVec` joined by the given
+    /// separator.
+    pub fn fmt_joined<'a>(&'a self, separator: &'a str) -> DisplaySlice<'a, T> {
+        DisplaySlice::new(self, separator)
+    }
+}
+
+impl<const N: usize, T: Default> InlineVec<N, T> {
+    /// Resizes this `InlineVec` so its length is `new_len`, clamped to the capacity. New slots are
+    /// filled with `T::default()`, and if `new_len` is smaller than the current length then the
+    /// vector is truncated, dropping the excess elements.
+    pub fn resize_default(&mut self, new_len: usize) {
+        let new_len = new_len.min(self.capacity());
+
+        if new_len < self.len {
+            self.truncate(new_len);
+        } else {
+            while self.len < new_len {
+                let _ = self.push(T::default());
+            }
+        }
+    }
+}
+
+impl<const N: usize, T: PartialEq> InlineVec<N, T> {
+    /// Removes all the elements equal to `value`, preserving the order of the remaining elements.
+    /// Returns the amount of elements removed.
+    pub fn remove_all(&mut self, value: &T) -> usize {
+        let len = self.len();
+        self.retain(|element| element != value);
+        len - self.len()
+    }
+}
+
+impl<const N: usize, T> InlineVec<N, T>
+where
+    T: for<'a> Sum<&'a T>,
+{
+    /// Returns the sum of all the elements in this `InlineVec`, which is zero if it's empty.
+    pub fn sum(&self) -> T {
+        self.iter().sum()
+    }
+}
+
+impl<const N: usize, T: Ord> InlineVec<N, T> {
+    /// Sorts this `InlineVec` and removes all duplicate elements (not just consecutive ones), returning
+    /// it as a sorted set of unique elements.
+    pub fn into_unique_sorted(mut self) -> Self {
+        self.sort_unstable();
+
+        // Move each element that differs from the last kept one to the end of the kept ones. Since
+        // the elements are sorted, the ones left past the kept ones are all duplicates.
+        let mut write = 0;
+        for read in 0..self.len() {
+            if write == 0 || self[read] != self[write - 1] {
+                self.swap(read, write);
+                write += 1;
+            }
+        }
+
+        self.truncate(write);
+        self
+    }
+
+    /// Returns a reference to the smallest element in this `InlineVec`, or `None` if it's empty.
+    pub fn min_element(&self) -> Option<&T> {
+        self.iter().min()
+    }
+
+    /// Returns a reference to the largest element in this `InlineVec`, or `None` if it's empty.
+    pub fn max_element(&self) -> Option<&T> {
+        self.iter().max()
+    }
 }
 
 impl<const N: usize> io::Write for InlineVec<N, u8> {
@@ -392,9 +561,9 @@ impl<const N: usize, T> Drop for IntoIter<N, T> {
 
 #[cfg(test)]
 mod tests {
-    use std::{io::Write, ops::Deref};
+    use std::{io::Write, net::SocketAddr, ops::Deref};
 
-    use crate::test_utils::DropChecker;
+    use crate::test_utils::{AddressChecked, DefaultDC, DropChecker};
 
     use super::InlineVec;
 
@@ -569,6 +738,262 @@ mod tests {
         dc.ensure_all_dropped();
     }
 
+    #[test]
+    fn test_as_array() {
+        let mut vec = InlineVec::<3, u8>::new();
+        assert_eq!(vec.as_array(), None);
+
+        vec.push(1);
+        vec.push(2);
+        assert_eq!(vec.as_array(), None);
+
+        vec.push(3);
+        assert_eq!(vec.as_array(), Some(&[1, 2, 3]));
+
+        vec.pop();
+        assert_eq!(vec.as_array(), None);
+
+        let empty = InlineVec::<0, u8>::new();
+        assert_eq!(empty.as_array(), Some(&[]));
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut vec = InlineVec::<8, _>::new();
+        vec.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        vec.retain(|_| true);
+        assert_eq!(vec.deref(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+        vec.retain(|x| x % 3 != 0);
+        assert_eq!(vec.deref(), &[1, 2, 4, 5, 7, 8]);
+
+        vec.retain(|x| *x > 4);
+        assert_eq!(vec.deref(), &[5, 7, 8]);
+
+        vec.retain(|x| *x < 8);
+        assert_eq!(vec.deref(), &[5, 7]);
+
+        vec.retain(|_| false);
+        assert_eq!(vec.deref(), &[]);
+
+        vec.retain(|_| true);
+        assert_eq!(vec.deref(), &[]);
+    }
+
+    #[test]
+    fn test_retain_drops() {
+        let mut dc = DropChecker::new();
+        let mut vec = InlineVec::<6, _>::new();
+
+        for i in 0..6 {
+            assert_eq!(vec.push(dc.track(i)), None);
+        }
+
+        let mut visited = Vec::new();
+        vec.retain(|x| {
+            visited.push(x.value);
+            true
+        });
+        assert_eq!(visited, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(vec.iter().map(|x| x.value).collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+
+        vec.retain(|x| x.value != 0 && x.value != 3 && x.value != 4);
+        assert_eq!(vec.iter().map(|x| x.value).collect::<Vec<_>>(), [1, 2, 5]);
+
+        drop(vec);
+        dc.ensure_all_dropped();
+    }
+
+    #[test]
+    fn test_retain_moves_only_after_first_removed() {
+        let mut vec = InlineVec::<6, _>::new();
+        for i in 0..6 {
+            assert!(vec.push(AddressChecked::new(i)).is_none());
+        }
+        vec.iter().for_each(AddressChecked::settle);
+
+        vec.retain(|x| {
+            assert!(!x.was_moved());
+            true
+        });
+        assert!(vec.iter().all(|x| !x.was_moved()));
+
+        vec.retain(|x| x.value != 2);
+        let moved = vec.iter().map(|x| (x.value, x.was_moved())).collect::<Vec<_>>();
+        assert_eq!(moved, [(0, false), (1, false), (3, true), (4, true), (5, true)]);
+    }
+
+    #[test]
+    fn test_resize_default() {
+        let alive_count = || DefaultDC::with_checker(|dc| dc.alive_count());
+        let mut vec = InlineVec::<5, DefaultDC>::new();
+
+        vec.resize_default(3);
+        assert_eq!(vec.len(), 3);
+        assert_eq!(alive_count(), 3);
+        assert!(vec.iter().all(|x| x.0.value == DefaultDC::DEFAULT_VALUE));
+
+        vec[1].0.value = 1;
+        vec.resize_default(10);
+        assert_eq!(vec.len(), 5);
+        assert_eq!(alive_count(), 5);
+        assert_eq!(vec[1].0.value, 1);
+
+        vec.resize_default(2);
+        assert_eq!(vec.len(), 2);
+        assert_eq!(alive_count(), 2);
+        assert_eq!(vec[1].0.value, 1);
+
+        vec.resize_default(2);
+        assert_eq!(alive_count(), 2);
+
+        vec.resize_default(0);
+        assert!(vec.is_empty());
+        DefaultDC::with_checker(|dc| dc.ensure_all_dropped());
+    }
+
+    #[test]
+    fn test_clone_copy() {
+        let mut vec = InlineVec::<5, u32>::new();
+        assert_eq!(vec.clone_copy(), vec);
+
+        vec.extend_from_slice_copied(&[1, 2, 3]);
+        let mut copy = vec.clone_copy();
+        assert_eq!(copy, vec.clone());
+        assert_eq!(copy.as_slice(), &[1, 2, 3]);
+
+        copy[0] = 10;
+        copy.push(4);
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+        assert_eq!(copy.as_slice(), &[10, 2, 3, 4]);
+
+        copy.extend_from_slice_copied(&[5]);
+        assert_eq!(copy.clone_copy().as_slice(), &[10, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_into_unique_sorted() {
+        let mut vec = InlineVec::<16, i32>::new();
+        vec.extend_from_slice_copied(&[5, 3, 5, 1, 3, 3, 9, 1, 5, 0, 9, 3, 1, 5, 0, 7]);
+        assert_eq!(vec.into_unique_sorted().as_slice(), &[0, 1, 3, 5, 7, 9]);
+
+        let mut vec = InlineVec::<8, i32>::new();
+        vec.extend_from_slice_copied(&[4, 4, 4, 4, 4]);
+        assert_eq!(vec.into_unique_sorted().as_slice(), &[4]);
+
+        let mut vec = InlineVec::<8, i32>::new();
+        vec.extend_from_slice_copied(&[3, 2, 1]);
+        assert_eq!(vec.into_unique_sorted().as_slice(), &[1, 2, 3]);
+
+        assert!(InlineVec::<8, i32>::new().into_unique_sorted().is_empty());
+    }
+
+    #[test]
+    fn test_into_unique_sorted_drops() {
+        let mut dc = DropChecker::new();
+        let mut vec = InlineVec::<8, _>::new();
+        for value in [2, 1, 2, 3, 1, 2] {
+            vec.push(dc.track(value));
+        }
+
+        let vec = vec.into_unique_sorted();
+        assert_eq!(vec.iter().map(|v| v.value).collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        drop(vec);
+        dc.ensure_all_dropped();
+    }
+
+    #[test]
+    fn test_sum_min_max() {
+        let mut vec = InlineVec::<8, i32>::new();
+        vec.extend_from_slice_copied(&[4, -2, 9, 0, 7]);
+        assert_eq!(vec.sum(), 18);
+        assert_eq!(vec.min_element(), Some(&-2));
+        assert_eq!(vec.max_element(), Some(&9));
+
+        let vec = InlineVec::<8, u64>::new();
+        assert_eq!(vec.sum(), 0);
+        assert_eq!(vec.min_element(), None);
+        assert_eq!(vec.max_element(), None);
+    }
+
+    #[test]
+    fn test_set() {
+        let mut dc = DropChecker::new();
+        let mut vec = InlineVec::<4, _>::new();
+        vec.push(dc.track(1));
+        vec.push(dc.track(2));
+        vec.push(dc.track(3));
+
+        let old = vec.set(1, dc.track(20));
+        assert_eq!(old.map(|v| v.value), Some(2));
+        assert_eq!(vec.iter().map(|v| v.value).collect::<Vec<_>>(), vec![1, 20, 3]);
+
+        assert!(vec.set(3, dc.track(40)).is_none());
+        assert!(vec.set(200, dc.track(50)).is_none());
+        assert_eq!(vec.iter().map(|v| v.value).collect::<Vec<_>>(), vec![1, 20, 3]);
+
+        drop(vec);
+        dc.ensure_all_dropped();
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let mut dc = DropChecker::new();
+        let mut vec = InlineVec::<10, _>::new();
+        for value in [3, 1, 3, 2, 3, 4, 1, 3, 5, 3] {
+            vec.push(dc.track(value));
+        }
+
+        let three = dc.track(3);
+        assert_eq!(vec.remove_all(&three), 5);
+        assert_eq!(vec.iter().map(|v| v.value).collect::<Vec<_>>(), vec![1, 2, 4, 1, 5]);
+        assert_eq!(vec.remove_all(&three), 0);
+
+        let one = dc.track(1);
+        assert_eq!(vec.remove_all(&one), 2);
+        assert_eq!(vec.iter().map(|v| v.value).collect::<Vec<_>>(), vec![2, 4, 5]);
+
+        drop((vec, three, one));
+        dc.ensure_all_dropped();
+    }
+
+    #[test]
+    fn test_extend_returning() {
+        let mut vec = InlineVec::<4, i32>::new();
+        vec.push(1);
+
+        let mut rest = vec.extend_returning(2..10);
+        assert_eq!(vec.as_slice(), &[1, 2, 3, 4]);
+        assert_eq!(rest.next(), Some(5));
+        assert_eq!(rest.collect::<Vec<_>>(), vec![6, 7, 8, 9]);
+
+        let mut rest = vec.extend_returning([10, 11]);
+        assert_eq!(rest.next(), Some(10));
+        assert_eq!(rest.next(), Some(11));
+        assert_eq!(vec.as_slice(), &[1, 2, 3, 4]);
+
+        let mut vec = InlineVec::<4, i32>::new();
+        let mut rest = vec.extend_returning([1, 2]);
+        assert_eq!(rest.next(), None);
+        assert_eq!(vec.as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_extend_returning_drops() {
+        let mut dc = DropChecker::new();
+        let mut vec = InlineVec::<3, _>::new();
+
+        let values = (0..6).map(|i| dc.track(i)).collect::<Vec<_>>();
+        let rest = vec.extend_returning(values);
+        assert_eq!(vec.iter().map(|v| v.value).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(rest.map(|v| v.value).collect::<Vec<_>>(), vec![3, 4, 5]);
+
+        drop(vec);
+        dc.ensure_all_dropped();
+    }
+
     #[test]
     fn test_write() {
         let mut vec = InlineVec::<5, u8>::new();
@@ -594,4 +1019,18 @@ mod tests {
         assert!(vec.write(&[100, 101, 102, 103, 104]).is_ok_and(|v| v == 0));
         assert_eq!(vec.deref(), &[4, 20, 69, 7, 90]);
     }
+
+    #[test]
+    fn test_fmt_joined() {
+        let mut vec = InlineVec::<4, SocketAddr>::new();
+        assert_eq!(vec.fmt_joined(", ").to_string(), "");
+
+        vec.push(SocketAddr::from(([127, 0, 0, 1], 5995)));
+        assert_eq!(vec.fmt_joined(", ").to_string(), "127.0.0.1:5995");
+
+        vec.push(SocketAddr::from(([0u16, 0, 0, 0, 0, 0, 0, 1], 8080)));
+        vec.push(SocketAddr::from(([10, 0, 0, 2], 443)));
+        assert_eq!(vec.fmt_joined(", ").to_string(), "127.0.0.1:5995, [::1]:8080, 10.0.0.2:443");
+        assert_eq!(vec.fmt_joined(";").to_string(), "127.0.0.1:5995;[::1]:8080;10.0.0.2:443");
+    }
 }
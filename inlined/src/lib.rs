@@ -51,12 +51,16 @@
 mod test_utils;
 
 pub mod compact_vec;
+pub mod display_slice;
+pub mod error;
 pub mod inline_string;
 pub mod inline_vec;
 pub mod tiny_string;
 pub mod tiny_vec;
 
 pub use compact_vec::CompactVec;
+pub use display_slice::DisplaySlice;
+pub use error::CapacityError;
 pub use inline_string::InlineString;
 pub use inline_vec::InlineVec;
 pub use tiny_string::TinyString;
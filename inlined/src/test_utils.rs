@@ -1,6 +1,8 @@
 use std::{
+    cell::{Cell, RefCell},
     fmt::Debug,
     ops::{Deref, DerefMut},
+    ptr,
     sync::{atomic::AtomicBool, Arc},
 };
 
@@ -43,6 +45,14 @@ impl DropChecker {
         DC { tracker, value }
     }*/
 
+    /// Gets the amount of tracked values that haven't been dropped yet.
+    pub fn alive_count(&self) -> usize {
+        self.tracked
+            .iter()
+            .filter(|t| !t.was_dropped.load(std::sync::atomic::Ordering::Relaxed))
+            .count()
+    }
+
     pub fn ensure_all_dropped(&mut self) {
         for t in self.tracked.drain(..) {
             if !t.was_dropped.load(std::sync::atomic::Ordering::Relaxed) {
@@ -109,3 +119,64 @@ impl<T: PartialEq> PartialEq for DC<T> {
 }
 
 impl<T: Eq> Eq for DC<T> {}
+
+impl<T: PartialOrd> PartialOrd for DC<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Ord> Ord for DC<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+thread_local! {
+    static DEFAULT_DROP_CHECKER: RefCell<DropChecker> = const { RefCell::new(DropChecker::new()) };
+}
+
+/// A [`DC`] whose [`Default`] implementation tracks a new value with the current thread's default
+/// [`DropChecker`], for testing code that creates its values with `T::default()`.
+pub struct DefaultDC(pub DC<i32>);
+
+impl DefaultDC {
+    /// The value held by a newly created `DefaultDC`.
+    pub const DEFAULT_VALUE: i32 = 7;
+
+    /// Runs `f` with the current thread's default [`DropChecker`].
+    pub fn with_checker<R, F: FnOnce(&mut DropChecker) -> R>(f: F) -> R {
+        DEFAULT_DROP_CHECKER.with_borrow_mut(f)
+    }
+}
+
+impl Default for DefaultDC {
+    fn default() -> Self {
+        Self::with_checker(|dc| Self(dc.track(Self::DEFAULT_VALUE)))
+    }
+}
+
+/// A value that remembers the address it was settled at, for detecting when it gets moved.
+pub struct AddressChecked<T> {
+    home: Cell<*const ()>,
+    pub value: T,
+}
+
+impl<T> AddressChecked<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            home: Cell::new(ptr::null()),
+            value,
+        }
+    }
+
+    /// Remembers the current address as this value's home.
+    pub fn settle(&self) {
+        self.home.set(self as *const Self as *const ());
+    }
+
+    /// Returns whether this value is no longer at the address it was last settled at.
+    pub fn was_moved(&self) -> bool {
+        self.home.get() != self as *const Self as *const ()
+    }
+}
@@ -1,7 +1,10 @@
 use core::fmt;
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    str::FromStr,
+};
 
-use super::TinyVec;
+use super::{CapacityError, TinyVec};
 
 /// A UTF-8–encoded, inline string. Similar to [`String`], but stores chars inline instead of
 /// allocating on the heap. Similar to [`InlineString`](super::InlineString), but has an `u8`
@@ -13,7 +16,7 @@ use super::TinyVec;
 /// this type to make use of the stack, avoiding memory allocations and improving cache hits.
 ///
 /// `N` should be strictly lower than 256.
-#[derive(Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct TinyString<const N: usize> {
     inner: TinyVec<N, u8>,
 }
@@ -32,6 +35,14 @@ impl<const N: usize> DerefMut for TinyString<N> {
     }
 }
 
+impl<const N: usize> Clone for TinyString<N> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone_copy(),
+        }
+    }
+}
+
 impl<const N: usize> fmt::Debug for TinyString<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(self.deref(), f)
@@ -93,6 +104,20 @@ impl<const N: usize> TinyString<N> {
         byte_count as u8
     }
 
+    /// Appends a given string slice onto the end of this `TinyString` only if it fits entirely.
+    ///
+    /// Unlike [`push_str`](Self::push_str), this never appends partial content. If the string
+    /// doesn't fit, then this `TinyString` is left unchanged and a [`CapacityError`] is returned.
+    pub fn push_str_all(&mut self, string: &str) -> Result<(), CapacityError> {
+        let remaining_capacity = (self.capacity() - self.len()) as usize;
+        if string.len() > remaining_capacity {
+            return Err(CapacityError::new(string.len(), remaining_capacity));
+        }
+
+        self.inner.extend_from_slice_copied(string.as_bytes());
+        Ok(())
+    }
+
     /// Appends the given [`char`] to the end of this `TinyString`, returning how many bytes were
     /// appended.
     ///
@@ -142,6 +167,26 @@ impl<const N: usize> TinyString<N> {
         }
     }
 
+    /// Normalizes a user-entered code in this `TinyString`, such as one pasted with surrounding
+    /// whitespace or wrapped across lines, by removing all whitespace from it in place. The case is
+    /// left untouched, since codes may be case-sensitive.
+    pub fn normalize_code(&mut self) {
+        let mut new_len = 0;
+        let mut index = 0;
+
+        while let Some(c) = self.as_str()[index..].chars().next() {
+            let char_len = c.len_utf8();
+            if !c.is_whitespace() {
+                self.inner.copy_within(index..(index + char_len), new_len);
+                new_len += char_len;
+            }
+
+            index += char_len;
+        }
+
+        unsafe { self.inner.set_len(new_len as u8) };
+    }
+
     /// Returns a mutable reference to this [`TinyString`]'s internal [`TinyVec`].
     ///
     /// # Safety
@@ -175,6 +220,18 @@ impl<const N: usize> From<&str> for TinyString<N> {
     }
 }
 
+/// Parses a `TinyString` from a string slice. Unlike [`From<&str>`], which truncates the string if
+/// it doesn't fit, this returns a [`CapacityError`] instead.
+impl<const N: usize> FromStr for TinyString<N> {
+    type Err = CapacityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut result = TinyString::new();
+        result.push_str_all(s)?;
+        Ok(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fmt::Write, ops::Deref};
@@ -182,6 +239,7 @@ mod tests {
     const SPANISH: &str = "la brújula léntamente me guía hacia la ubicación de los pingüinos... ¿Cómo y por qué lo hace?";
 
     use super::TinyString;
+    use crate::CapacityError;
 
     #[test]
     fn test_push_pop() {
@@ -306,6 +364,26 @@ mod tests {
         TinyString::<4>::from("ü").truncate(1);
     }
 
+    #[test]
+    fn test_normalize_code() {
+        let mut s = TinyString::<32>::from("  \tAbC-123\n-xyZ \r\n");
+        s.normalize_code();
+        assert_eq!(s.deref(), "AbC-123-xyZ");
+
+        let mut s = TinyString::<32>::from("already-Normal");
+        s.normalize_code();
+        assert_eq!(s.deref(), "already-Normal");
+
+        let mut s = TinyString::<32>::from(" ñan\u{a0}dú\u{3000}");
+        s.normalize_code();
+        assert_eq!(s.deref(), "ñandú");
+
+        let mut s = TinyString::<32>::from(" \n\t ");
+        s.normalize_code();
+        assert_eq!(s.deref(), "");
+        assert!(s.is_empty());
+    }
+
     #[test]
     fn test_write() {
         let mut s = TinyString::<16>::new();
@@ -325,4 +403,37 @@ mod tests {
         assert_eq!(write!(s, "{}", 123), Ok(()));
         assert_eq!(s.deref(), "Goodbye: 123");
     }
+
+    #[test]
+    fn test_push_str_all() {
+        let mut s = TinyString::<8>::new();
+
+        assert_eq!(s.push_str_all("Hello"), Ok(()));
+        assert_eq!(s.deref(), "Hello");
+
+        assert_eq!(s.push_str_all(", world"), Err(CapacityError::new(7, 3)));
+        assert_eq!(s.deref(), "Hello");
+
+        assert_eq!(s.push_str_all("áé"), Err(CapacityError::new(4, 3)));
+        assert_eq!(s.deref(), "Hello");
+
+        assert_eq!(s.push_str_all("!á"), Ok(()));
+        assert_eq!(s.deref(), "Hello!á");
+
+        assert_eq!(s.push_str_all(""), Ok(()));
+        assert_eq!(s.push_str_all("?"), Err(CapacityError::new(1, 0)));
+        assert_eq!(s.deref(), "Hello!á");
+    }
+
+    #[test]
+    fn test_from_str() {
+        let s: TinyString<8> = "Hello".parse().unwrap();
+        assert_eq!(s.deref(), "Hello");
+
+        let s: TinyString<8> = "12345678".parse().unwrap();
+        assert_eq!(s.deref(), "12345678");
+
+        assert_eq!("123456789".parse::<TinyString<8>>(), Err(CapacityError::new(9, 8)));
+        assert_eq!("1234567á".parse::<TinyString<8>>(), Err(CapacityError::new(9, 8)));
+    }
 }
@@ -2,6 +2,7 @@ mod packet;
 mod state;
 mod state_machine;
 
+use std::fmt;
 use std::io;
 use std::io::Error;
 use std::net::IpAddr;
@@ -76,12 +77,74 @@ pub enum PuncherAction {
     Timeout,
 }
 
+/// A coarse description of how far along the hole-punching process is, computed from the
+/// aggregate state of all the lanes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PunchPhase {
+    /// No lane has heard back from the remote peer yet.
+    Connecting,
+
+    /// At least one lane has heard back from the remote peer, but no lane has been selected yet.
+    Establishing,
+
+    /// (server only) A lane has been selected, but the state machine must keep going until the
+    /// client starts talking in the next protocol.
+    Selected,
+
+    /// (client only) A lane has been selected and the state machine has nothing else to do.
+    Done,
+
+    /// All the lanes have been blocked, or the timeout expired before a lane was selected.
+    Failed,
+}
+
+/// The amount of lanes in each state of a [`Puncher`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LaneCounts {
+    pub connecting: u16,
+    pub establishing: u16,
+    pub selected: u16,
+    pub closed: u16,
+    pub blocked: u16,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Ports {
     pub local: NonZeroU16,
     pub remote: NonZeroU16,
 }
 
+/// An error indicating the parameters passed to [`Puncher::try_new`] are invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PuncherConfigError {
+    /// The lane count is too large for the local port range, which would go over port 65535.
+    MyPortRangeOverflow,
+
+    /// The lane count is too large for the remote port range, which would go over port 65535.
+    RemotePortRangeOverflow,
+
+    /// The tick period is zero, which would make the puncher tick in a busy loop.
+    ZeroTickPeriod,
+
+    /// The tick period is longer than the timeout, so the puncher would time out before resending.
+    TickPeriodLongerThanTimeout,
+
+    /// The timeout is too large to be represented as an [`Instant`].
+    TimeoutTooLarge,
+}
+
+impl fmt::Display for PuncherConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MyPortRangeOverflow => write!(f, "lane_count would overflow my_port_start"),
+            Self::RemotePortRangeOverflow => write!(f, "lane_count would overflow remote_port_start"),
+            Self::ZeroTickPeriod => write!(f, "tick_period must be greater than zero"),
+            Self::TickPeriodLongerThanTimeout => write!(f, "tick_period must not be longer than timeout"),
+            Self::TimeoutTooLarge => write!(f, "timeout is too large"),
+        }
+    }
+}
+
 pub struct Puncher {
     my_port_start: NonZeroU16,
     remote_address: IpAddr,
@@ -98,6 +161,9 @@ pub struct Puncher {
 }
 
 impl Puncher {
+    /// Creates a new [`Puncher`].
+    ///
+    /// Panics if the parameters are invalid. See [`Puncher::try_new`] for a non-panicking version.
     pub fn new(
         is_server: bool,
         my_port_start: NonZeroU16,
@@ -107,18 +173,55 @@ impl Puncher {
         tick_period: Duration,
         timeout: Duration,
     ) -> Self {
+        let result = Self::try_new(
+            is_server,
+            my_port_start,
+            remote_address,
+            remote_port_start,
+            lane_count,
+            tick_period,
+            timeout,
+        );
+
+        result.unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates a new [`Puncher`], or returns an error if the lane count would overflow either port
+    /// range, or if `tick_period` is zero or longer than `timeout`.
+    pub fn try_new(
+        is_server: bool,
+        my_port_start: NonZeroU16,
+        remote_address: IpAddr,
+        remote_port_start: NonZeroU16,
+        lane_count: NonZeroU16,
+        tick_period: Duration,
+        timeout: Duration,
+    ) -> Result<Self, PuncherConfigError> {
         if my_port_start.checked_add(lane_count.get()).is_none() {
-            panic!("lane_count would overflow my_port_start");
+            return Err(PuncherConfigError::MyPortRangeOverflow);
         }
 
         if remote_port_start.checked_add(lane_count.get()).is_none() {
-            panic!("lane_count would overflow remote_port_start");
+            return Err(PuncherConfigError::RemotePortRangeOverflow);
+        }
+
+        if tick_period.is_zero() {
+            return Err(PuncherConfigError::ZeroTickPeriod);
+        }
+
+        if tick_period > timeout {
+            return Err(PuncherConfigError::TickPeriodLongerThanTimeout);
         }
 
+        // Since tick_period <= timeout, if the timeout instant can be represented then so can the
+        // first tick instant.
+        let now = Instant::now();
+        let timeout_instant = now.checked_add(timeout).ok_or(PuncherConfigError::TimeoutTooLarge)?;
+
         let mut lanes = Vec::with_capacity(lane_count.get() as usize);
         lanes.resize_with(lane_count.get() as usize, Lane::new);
 
-        Self {
+        Ok(Self {
             my_port_start,
             remote_address,
             remote_port_start,
@@ -129,9 +232,9 @@ impl Puncher {
             client_server_mismatch: false,
             selected_lane_index: None,
             tick_period,
-            next_tick_instant: Instant::now().checked_add(tick_period).unwrap(),
-            timeout_instant: Instant::now().checked_add(timeout).unwrap(),
-        }
+            next_tick_instant: now + tick_period,
+            timeout_instant,
+        })
     }
 
     pub fn my_port_start(&self) -> NonZeroU16 {
@@ -162,6 +265,45 @@ impl Puncher {
         !self.is_server
     }
 
+    /// Gets the amount of lanes in each state.
+    pub fn lane_counts(&self) -> LaneCounts {
+        let mut counts = LaneCounts::default();
+
+        for lane in &self.lanes {
+            let count = match lane.state {
+                LaneState::Connecting(_) => &mut counts.connecting,
+                LaneState::Establishing(_) => &mut counts.establishing,
+                LaneState::Selected(_) => &mut counts.selected,
+                LaneState::Closed => &mut counts.closed,
+                LaneState::Blocked(_) => &mut counts.blocked,
+            };
+
+            *count += 1;
+        }
+
+        counts
+    }
+
+    /// Gets the phase the hole-punching process is currently in. This is intended for reporting
+    /// progress, for driving the process use [`Puncher::poll`] instead.
+    pub fn phase(&self) -> PunchPhase {
+        if self.selected_lane_index.is_some() {
+            return match self.is_server {
+                true => PunchPhase::Selected,
+                false => PunchPhase::Done,
+            };
+        }
+
+        if self.open_lanes_count == 0 || Instant::now() >= self.timeout_instant {
+            return PunchPhase::Failed;
+        }
+
+        match self.lanes.iter().any(|lane| lane.state.is_establishing()) {
+            true => PunchPhase::Establishing,
+            false => PunchPhase::Connecting,
+        }
+    }
+
     pub fn next_tick_instant(&mut self) -> Option<Instant> {
         if (self.is_client() && self.selected_lane_index.is_some()) || self.open_lanes_count == 0 {
             return None;
@@ -219,11 +361,16 @@ impl Puncher {
         };
 
         // If the packet's source IP or port is not what we expect, block the lane due to interference.
-        if from.ip() != self.remote_address
-            || from.port() < self.remote_port_start.get()
-            || from.port() >= (self.remote_port_start.get() + self.lane_count.get())
-        {
-            self.block_lane(lane_index, BlockReason::Interference(from));
+        if from.ip() != self.remote_address {
+            let kind = InterferenceKind::WrongIp;
+            self.block_lane(lane_index, BlockReason::Interference { from, kind });
+            return None;
+        }
+
+        let remote_port_end = self.remote_port_start.get() + self.lane_count.get();
+        if from.port() < self.remote_port_start.get() || from.port() >= remote_port_end {
+            let kind = InterferenceKind::PortOutOfRange;
+            self.block_lane(lane_index, BlockReason::Interference { from, kind });
             return None;
         }
 
@@ -382,3 +529,200 @@ impl Puncher {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        num::NonZeroU16,
+        time::Duration,
+    };
+
+    use crate::{
+        BlockReason, InterferenceKind, LaneCounts, LaneState, PunchPhase, Puncher, PuncherConfigError,
+        MAX_REASONABLE_PAYLOAD,
+    };
+
+    const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    const CLIENT_PORT_START: NonZeroU16 = unsafe { NonZeroU16::new_unchecked(1000) };
+    const SERVER_PORT_START: NonZeroU16 = unsafe { NonZeroU16::new_unchecked(2000) };
+    const LANE_COUNT: NonZeroU16 = unsafe { NonZeroU16::new_unchecked(3) };
+
+    fn make_puncher(is_server: bool) -> Puncher {
+        let (my_port_start, remote_port_start) = match is_server {
+            true => (SERVER_PORT_START, CLIENT_PORT_START),
+            false => (CLIENT_PORT_START, SERVER_PORT_START),
+        };
+
+        Puncher::new(
+            is_server,
+            my_port_start,
+            LOCALHOST,
+            remote_port_start,
+            LANE_COUNT,
+            Duration::from_millis(1500),
+            Duration::from_secs(20),
+        )
+    }
+
+    /// Sends all the packets `from` has pending and delivers them to `to`.
+    fn deliver(from: &mut Puncher, to: &mut Puncher) {
+        let mut buf = [0u8; MAX_REASONABLE_PAYLOAD];
+        while let Some(send_info) = from.send_to(&mut buf, &[]) {
+            let source = SocketAddr::new(LOCALHOST, send_info.from_port.get());
+            to.received_from(Ok((&buf[..send_info.length], source)), send_info.to.port());
+        }
+    }
+
+    #[test]
+    fn test_phase_transitions() {
+        let mut client = make_puncher(false);
+        let mut server = make_puncher(true);
+
+        assert_eq!(client.phase(), PunchPhase::Connecting);
+        assert_eq!(server.phase(), PunchPhase::Connecting);
+
+        deliver(&mut client, &mut server);
+        assert_eq!(client.phase(), PunchPhase::Connecting);
+        assert_eq!(server.phase(), PunchPhase::Establishing);
+        assert_eq!(server.lane_counts().establishing, 3);
+
+        deliver(&mut server, &mut client);
+        assert_eq!(client.phase(), PunchPhase::Establishing);
+        assert_eq!(client.lane_counts().establishing, 3);
+
+        deliver(&mut client, &mut server);
+        assert_eq!(server.phase(), PunchPhase::Selected);
+        assert_eq!(
+            server.lane_counts(),
+            LaneCounts {
+                selected: 1,
+                closed: 2,
+                ..Default::default()
+            }
+        );
+
+        deliver(&mut server, &mut client);
+        assert_eq!(client.phase(), PunchPhase::Done);
+        assert_eq!(client.lane_counts().selected, 1);
+    }
+
+    #[test]
+    fn test_phase_failed() {
+        let mut client = make_puncher(false);
+
+        for i in 0..LANE_COUNT.get() {
+            let source = SocketAddr::new(LOCALHOST, SERVER_PORT_START.get() + i);
+            client.received_from(Ok((b"garbage", source)), CLIENT_PORT_START.get() + i);
+
+            let expected = match i + 1 == LANE_COUNT.get() {
+                true => PunchPhase::Failed,
+                false => PunchPhase::Connecting,
+            };
+            assert_eq!(client.phase(), expected);
+        }
+
+        assert_eq!(client.lane_counts().blocked, 3);
+    }
+
+    fn assert_interference(
+        puncher: &Puncher,
+        lane_index: usize,
+        expected_from: SocketAddr,
+        expected_kind: InterferenceKind,
+    ) {
+        match &puncher.lanes[lane_index].state {
+            LaneState::Blocked(BlockReason::Interference { from, kind }) => {
+                assert_eq!(*from, expected_from);
+                assert_eq!(*kind, expected_kind);
+            }
+            other => panic!(
+                "Expected lane {lane_index} to be blocked by interference, got {other:?}"
+            ),
+        }
+    }
+
+    #[test]
+    fn test_interference_wrong_ip() {
+        let mut server = make_puncher(true);
+        let from = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), CLIENT_PORT_START.get());
+        server.received_from(Ok((&[], from)), SERVER_PORT_START.get());
+
+        assert_interference(&server, 0, from, InterferenceKind::WrongIp);
+        assert_eq!(server.lane_counts().blocked, 1);
+    }
+
+    #[test]
+    fn test_interference_port_out_of_range() {
+        let mut server = make_puncher(true);
+
+        let below = SocketAddr::new(LOCALHOST, CLIENT_PORT_START.get() - 1);
+        server.received_from(Ok((&[], below)), SERVER_PORT_START.get());
+        assert_interference(&server, 0, below, InterferenceKind::PortOutOfRange);
+
+        let above = SocketAddr::new(LOCALHOST, CLIENT_PORT_START.get() + LANE_COUNT.get());
+        server.received_from(Ok((&[], above)), SERVER_PORT_START.get() + 1);
+        assert_interference(&server, 1, above, InterferenceKind::PortOutOfRange);
+
+        assert_eq!(server.lane_counts().blocked, 2);
+    }
+
+    fn try_make_puncher(
+        tick_period: Duration,
+        timeout: Duration,
+    ) -> Result<Puncher, PuncherConfigError> {
+        Puncher::try_new(
+            false,
+            CLIENT_PORT_START,
+            LOCALHOST,
+            SERVER_PORT_START,
+            LANE_COUNT,
+            tick_period,
+            timeout,
+        )
+    }
+
+    #[test]
+    fn test_try_new_tick_period() {
+        let result = try_make_puncher(Duration::ZERO, Duration::from_secs(20));
+        assert_eq!(result.err(), Some(PuncherConfigError::ZeroTickPeriod));
+
+        let result = try_make_puncher(Duration::from_secs(30), Duration::from_secs(20));
+        assert_eq!(result.err(), Some(PuncherConfigError::TickPeriodLongerThanTimeout));
+
+        let tick_period = Duration::from_millis(1500);
+        let mut puncher = try_make_puncher(tick_period, Duration::from_secs(20)).unwrap();
+        let first_tick = puncher.next_tick_instant().unwrap();
+        puncher.tick();
+        assert_eq!(puncher.next_tick_instant().unwrap(), first_tick + tick_period);
+    }
+
+    #[test]
+    fn test_try_new_port_overflow() {
+        let high_port = NonZeroU16::new(u16::MAX - 1).unwrap();
+        let tick_period = Duration::from_millis(1500);
+        let timeout = Duration::from_secs(20);
+
+        let result = Puncher::try_new(
+            false,
+            high_port,
+            LOCALHOST,
+            SERVER_PORT_START,
+            LANE_COUNT,
+            tick_period,
+            timeout,
+        );
+        assert_eq!(result.err(), Some(PuncherConfigError::MyPortRangeOverflow));
+
+        let result = Puncher::try_new(
+            false,
+            CLIENT_PORT_START,
+            LOCALHOST,
+            high_port,
+            LANE_COUNT,
+            tick_period,
+            timeout,
+        );
+        assert_eq!(result.err(), Some(PuncherConfigError::RemotePortRangeOverflow));
+    }
+}
@@ -155,7 +155,7 @@ pub enum BlockReason {
     BadPacket(PacketDataError),
 
     /// A packet was received from a wrong source IP or port.
-    Interference(SocketAddr),
+    Interference { from: SocketAddr, kind: InterferenceKind },
 
     /// The process was aborted locally.
     Aborted,
@@ -168,3 +168,15 @@ pub enum BlockReason {
     /// (or without having been `Establishing`).
     UnexpectedTransition,
 }
+
+/// The kind of interference detected on a lane, indicating what was wrong with a packet's source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterferenceKind {
+    /// The packet came from an IP address other than the remote peer's. This may indicate someone
+    /// is attempting to spoof packets.
+    WrongIp,
+
+    /// The packet came from the remote peer's IP address, but from a port outside its range of
+    /// lanes. This may indicate a NAT is remapping the remote peer's ports.
+    PortOutOfRange,
+}
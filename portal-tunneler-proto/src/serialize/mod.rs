@@ -30,6 +30,10 @@
 //! the length, followed by said amount of elements. Just like with strings, the [`SmallReadList`]
 //! and [`SmallWriteList`] types are provided, which wrap a [`Vec<T>`] and `&[T]` respectively.
 //!
+//! For choosing the width of the length prefix explicitly, the [`Prefixed<L, T>`] type wraps a
+//! string or list and serializes it with a length of type `L`, which may be [`u8`], [`u16`] or
+//! [`u32`]. This allows serializing large blobs with an [`u32`] length when needed.
+//!
 //! # Serialization of tuples
 //! [`ByteRead`] and [`ByteWrite`] are also implemented for any tuple of up to 5 elements, with all
 //! the element types being [`ByteRead`] and/or [`ByteWrite`]. This allows easily turning multiple
@@ -55,12 +59,14 @@ pub mod error;
 pub mod lists;
 pub mod net;
 pub mod nonzero;
+pub mod prefixed;
 pub mod primitives;
 pub mod string;
 pub mod tuples;
 pub mod u8_repr_enum;
 
 pub use lists::*;
+pub use prefixed::*;
 pub use string::*;
 pub use u8_repr_enum::*;
 
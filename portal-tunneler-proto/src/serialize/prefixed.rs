@@ -0,0 +1,247 @@
+use std::{
+    io::{self, Error, ErrorKind},
+    marker::PhantomData,
+};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::{ByteRead, ByteWrite};
+
+/// The maximum amount of elements or bytes to preallocate when reading a length-prefixed value.
+/// The length is read from the other side, so we avoid trusting it for large allocations and let
+/// the collection grow as the data actually arrives instead.
+const MAX_PREALLOCATE: usize = 4096;
+
+/// An integer type that can be used as the length prefix of a [`Prefixed`] value.
+///
+/// This is implemented for [`u8`], [`u16`] and [`u32`].
+pub trait LengthPrefix: ByteRead + ByteWrite {
+    /// The maximum length representable by this prefix.
+    const MAX_LEN: usize;
+
+    /// Converts a length into this prefix type, or returns `None` if it's larger than `MAX_LEN`.
+    fn from_len(len: usize) -> Option<Self>;
+
+    /// Converts this prefix into a length.
+    fn to_len(&self) -> usize;
+}
+
+impl LengthPrefix for u8 {
+    const MAX_LEN: usize = u8::MAX as usize;
+
+    fn from_len(len: usize) -> Option<Self> {
+        u8::try_from(len).ok()
+    }
+
+    fn to_len(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl LengthPrefix for u16 {
+    const MAX_LEN: usize = u16::MAX as usize;
+
+    fn from_len(len: usize) -> Option<Self> {
+        u16::try_from(len).ok()
+    }
+
+    fn to_len(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl LengthPrefix for u32 {
+    const MAX_LEN: usize = u32::MAX as usize;
+
+    fn from_len(len: usize) -> Option<Self> {
+        u32::try_from(len).ok()
+    }
+
+    fn to_len(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// A type that wraps a string or list and implements [`ByteRead`] and/or [`ByteWrite`] for it,
+/// using the length type `L` as the prefix that indicates its length.
+///
+/// The wrapped value may be `&str` or `&[T]` (write-only), or [`String`] or [`Vec<T>`]. This
+/// allows choosing between [`u8`], [`u16`] and [`u32`] length prefixes, with `Prefixed<u8, _>`
+/// and `Prefixed<u16, _>` being equivalent to the small and default serializations respectively.
+pub struct Prefixed<L, T> {
+    pub value: T,
+    length_type: PhantomData<L>,
+}
+
+impl<L: LengthPrefix, T> Prefixed<L, T> {
+    /// Wraps a value for serializing with a length prefix of type `L`.
+    pub const fn new(value: T) -> Self {
+        Self {
+            value,
+            length_type: PhantomData,
+        }
+    }
+
+    /// Unwraps this instance, returning the inner value.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+async fn write_len<L: LengthPrefix, W: AsyncWrite + Unpin + ?Sized>(len: usize, writer: &mut W) -> io::Result<()> {
+    match L::from_len(len) {
+        Some(prefix) => prefix.write(writer).await,
+        None => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Length {len} is too long for its prefix (> {})", L::MAX_LEN),
+        )),
+    }
+}
+
+impl<L: LengthPrefix> ByteWrite for Prefixed<L, &str> {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let bytes = self.value.as_bytes();
+        write_len::<L, W>(bytes.len(), writer).await?;
+        writer.write_all(bytes).await
+    }
+}
+
+impl<L: LengthPrefix> ByteWrite for Prefixed<L, String> {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        Prefixed::<L, _>::new(self.value.as_str()).write(writer).await
+    }
+}
+
+impl<L: LengthPrefix> ByteRead for Prefixed<L, String> {
+    async fn read<R: AsyncRead + Unpin + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let len = L::read(reader).await?.to_len();
+
+        let mut v = Vec::with_capacity(len.min(MAX_PREALLOCATE));
+        (&mut *reader).take(len as u64).read_to_end(&mut v).await?;
+        if v.len() != len {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "Prefixed string ended unexpectedly"));
+        }
+
+        match String::from_utf8(v) {
+            Ok(s) => Ok(Self::new(s)),
+            Err(_) => Err(Error::new(ErrorKind::InvalidData, "Prefixed string is not valid UTF-8")),
+        }
+    }
+}
+
+impl<L: LengthPrefix, T: ByteWrite> ByteWrite for Prefixed<L, &[T]> {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        write_len::<L, W>(self.value.len(), writer).await?;
+        for ele in self.value.iter() {
+            ele.write(writer).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<L: LengthPrefix, T: ByteWrite> ByteWrite for Prefixed<L, Vec<T>> {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        Prefixed::<L, _>::new(self.value.as_slice()).write(writer).await
+    }
+}
+
+impl<L: LengthPrefix, T: ByteRead> ByteRead for Prefixed<L, Vec<T>> {
+    async fn read<R: AsyncRead + Unpin + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let len = L::read(reader).await?.to_len();
+
+        let mut v = Vec::with_capacity(len.min(MAX_PREALLOCATE));
+        for _ in 0..len {
+            v.push(T::read(reader).await?);
+        }
+
+        Ok(Self::new(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use super::{LengthPrefix, Prefixed};
+    use crate::serialize::{ByteRead, ByteWrite};
+
+    async fn round_trip_string<L: LengthPrefix>(len: usize) {
+        let s: String = (0..len).map(|i| (b'a' + (i % 26) as u8) as char).collect();
+
+        let mut buf = Vec::new();
+        Prefixed::<L, _>::new(s.as_str()).write(&mut buf).await.unwrap();
+        assert_eq!(buf.len(), std::mem::size_of::<L>() + len);
+
+        let mut reader = buf.as_slice();
+        let result = Prefixed::<L, String>::read(&mut reader).await.unwrap();
+        assert_eq!(result.into_inner(), s);
+        assert!(reader.is_empty());
+    }
+
+    async fn round_trip_list<L: LengthPrefix>(len: usize) {
+        let list: Vec<u16> = (0..len).map(|i| i as u16).collect();
+
+        let mut buf = Vec::new();
+        Prefixed::<L, _>::new(list.as_slice()).write(&mut buf).await.unwrap();
+        assert_eq!(buf.len(), std::mem::size_of::<L>() + len * 2);
+
+        let mut reader = buf.as_slice();
+        let result = Prefixed::<L, Vec<u16>>::read(&mut reader).await.unwrap();
+        assert_eq!(result.into_inner(), list);
+        assert!(reader.is_empty());
+    }
+
+    async fn assert_too_long<L: LengthPrefix>() {
+        let s = "a".repeat(L::MAX_LEN + 1);
+        let mut buf = Vec::new();
+        let result = Prefixed::<L, _>::new(s.as_str()).write(&mut buf).await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn test_u8_prefix() {
+        for len in [0, 1, 254, 255] {
+            round_trip_string::<u8>(len).await;
+            round_trip_list::<u8>(len).await;
+        }
+
+        assert_too_long::<u8>().await;
+    }
+
+    #[tokio::test]
+    async fn test_u16_prefix() {
+        for len in [0, 1, 255, 256, 65534, 65535] {
+            round_trip_string::<u16>(len).await;
+            round_trip_list::<u16>(len).await;
+        }
+
+        assert_too_long::<u16>().await;
+    }
+
+    #[tokio::test]
+    async fn test_u32_prefix() {
+        for len in [0, 1, 255, 256, 65535, 65536, 100000] {
+            round_trip_string::<u32>(len).await;
+            round_trip_list::<u32>(len).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_u16_matches_default_serialization() {
+        let mut prefixed_buf = Vec::new();
+        Prefixed::<u16, _>::new("Hello!").write(&mut prefixed_buf).await.unwrap();
+
+        let mut default_buf = Vec::new();
+        "Hello!".write(&mut default_buf).await.unwrap();
+
+        assert_eq!(prefixed_buf, default_buf);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_string() {
+        let buf = [5u8, b'a', b'b'];
+        let result = Prefixed::<u8, String>::read(&mut buf.as_slice()).await;
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::UnexpectedEof);
+    }
+}
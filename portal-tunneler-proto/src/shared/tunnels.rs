@@ -101,6 +101,84 @@ impl ByteRead for TunnelTargetType {
     }
 }
 
+impl U8ReprEnum for TunnelSide {
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Local),
+            1 => Some(Self::Remote),
+            _ => None,
+        }
+    }
+
+    fn into_u8(self) -> u8 {
+        match self {
+            Self::Local => 0,
+            Self::Remote => 1,
+        }
+    }
+}
+
+impl ByteWrite for TunnelSide {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.into_u8().write(writer).await
+    }
+}
+
+impl ByteRead for TunnelSide {
+    async fn read<R: AsyncRead + Unpin + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        match Self::from_u8(u8::read(reader).await?) {
+            Some(side) => Ok(side),
+            None => Err(Error::new(ErrorKind::InvalidData, "Invalid TunnelSide type byte")),
+        }
+    }
+}
+
+impl ByteWrite for TunnelTarget {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.as_type().write(writer).await?;
+        match self {
+            Self::Address(address) => address.write(writer).await,
+            Self::Socks => Ok(()),
+        }
+    }
+}
+
+impl ByteRead for TunnelTarget {
+    async fn read<R: AsyncRead + Unpin + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        match TunnelTargetType::read(reader).await? {
+            TunnelTargetType::Static => Ok(Self::Address(AddressOrDomainname::read(reader).await?)),
+            TunnelTargetType::Socks => Ok(Self::Socks),
+        }
+    }
+}
+
+impl ByteWrite for TunnelSpec {
+    async fn write<W: AsyncWrite + Unpin + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let index = match u32::try_from(self.index) {
+            Ok(index) => index,
+            Err(_) => return Err(Error::new(ErrorKind::InvalidInput, "TunnelSpec index is too large")),
+        };
+
+        (index, self.side, &self.target, &self.listen_address).write(writer).await
+    }
+}
+
+impl ByteRead for TunnelSpec {
+    async fn read<R: AsyncRead + Unpin + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let index = u32::read(reader).await? as usize;
+        let side = TunnelSide::read(reader).await?;
+        let target = TunnelTarget::read(reader).await?;
+        let listen_address = AddressOrDomainname::read(reader).await?;
+
+        Ok(Self {
+            index,
+            side,
+            target,
+            listen_address,
+        })
+    }
+}
+
 /// An number that uniquely identifies a remote tunnel.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct RemoteTunnelID(pub u32);
@@ -116,3 +194,72 @@ impl ByteWrite for RemoteTunnelID {
         self.0.write(writer).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+        num::NonZeroU16,
+    };
+
+    use super::{TunnelSide, TunnelSpec, TunnelTarget};
+    use crate::{
+        serialize::{ByteRead, ByteWrite},
+        shared::AddressOrDomainname,
+    };
+
+    async fn round_trip(spec: TunnelSpec) {
+        let mut buf = Vec::new();
+        spec.write(&mut buf).await.unwrap();
+
+        let mut reader = buf.as_slice();
+        let result = TunnelSpec::read(&mut reader).await.unwrap();
+        assert_eq!(result, spec);
+        assert!(reader.is_empty());
+    }
+
+    fn domainname(name: &str, port: u16) -> AddressOrDomainname {
+        AddressOrDomainname::Domainname(String::from(name), NonZeroU16::new(port).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_local_static_spec() {
+        round_trip(TunnelSpec {
+            index: 0,
+            side: TunnelSide::Local,
+            target: TunnelTarget::Address(AddressOrDomainname::Address(SocketAddr::from((Ipv4Addr::LOCALHOST, 5555)))),
+            listen_address: AddressOrDomainname::Address(SocketAddr::from((Ipv6Addr::UNSPECIFIED, 4444))),
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_remote_static_spec() {
+        round_trip(TunnelSpec {
+            index: 7,
+            side: TunnelSide::Remote,
+            target: TunnelTarget::Address(domainname("example.com", 80)),
+            listen_address: domainname("localhost", 8080),
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_socks_specs() {
+        for side in [TunnelSide::Local, TunnelSide::Remote] {
+            round_trip(TunnelSpec {
+                index: 3,
+                side,
+                target: TunnelTarget::Socks,
+                listen_address: AddressOrDomainname::Address(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 1080))),
+            })
+            .await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalid_side() {
+        let result = TunnelSide::read(&mut [2u8].as_slice()).await;
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+}
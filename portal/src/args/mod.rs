@@ -1,11 +1,13 @@
 use std::{
     net::{IpAddr, SocketAddr},
     num::NonZeroU16,
+    time::Duration,
 };
 
 mod addresses;
 mod parser;
 mod ports;
+mod timeouts;
 mod tunnels;
 
 pub use addresses::*;
@@ -13,6 +15,7 @@ use inlined::CompactVec;
 pub use parser::*;
 use portal_tunneler_proto::shared::TunnelSpec;
 pub use ports::*;
+pub use timeouts::*;
 pub use tunnels::*;
 
 /// The default amount of lanes (sequential ports) to use when hole-punching.
@@ -47,6 +50,9 @@ pub enum ArgumentsRequest {
 
     /// Run with the provided arguments.
     Run(StartupArguments),
+
+    /// Check that everything needed for running with the provided arguments works, then exit.
+    HealthCheck(StartupArguments),
 }
 
 /// Specifies the information on how the program should run.
@@ -58,6 +64,12 @@ pub struct StartupArguments {
     /// Whether to not print any information to stdout.
     pub silent: bool,
 
+    /// Whether to print a summary of the tunneled connections when a session ends.
+    pub summary: bool,
+
+    /// If `Some`, SOCKS clients that don't complete their request within this long are dropped.
+    pub socks_timeout: Option<Duration>,
+
     /// The method to use for connecting to the remote peer.
     pub connect_method: ConnectMethod,
 
@@ -82,19 +94,29 @@ pub struct PunchConfig {
     /// Our publicly-visible IP address. If `None`, then it will be queried with a public API.
     pub my_ip: Option<IpAddr>,
 
+    /// Whether to never query our public IP address with a public API. If set, `my_ip` must be
+    /// specified.
+    pub no_public_ip: bool,
+
     /// The first port to try to bind. If `None`, a random port will be requested to the OS.
     pub port_start: Option<NonZeroU16>,
 
     /// The amount of sequential ports to bind.
     pub lane_count: NonZeroU16,
+
+    /// If `Some`, a failed punch is retried with more lanes, up to this many. Only the lanes in both
+    /// connection codes are punched, so this has no effect unless the friend also retries with more.
+    pub max_lane_count: Option<NonZeroU16>,
 }
 
 impl PunchConfig {
     pub const fn new() -> Self {
         Self {
             my_ip: None,
+            no_public_ip: false,
             port_start: None,
             lane_count: DEFAULT_LANE_COUNT,
+            max_lane_count: None,
         }
     }
 }
@@ -118,11 +140,14 @@ impl StartupMode {
 
 /// Specifies configuration when starting in server mode.
 #[derive(Debug, PartialEq)]
-pub struct StartServerConfig {}
+pub struct StartServerConfig {
+    /// If `Some`, the server stops waiting for a connection after this long without accepting one.
+    pub accept_timeout: Option<Duration>,
+}
 
 impl StartServerConfig {
     pub const fn new() -> Self {
-        Self {}
+        Self { accept_timeout: None }
     }
 }
 
@@ -131,19 +156,34 @@ impl StartServerConfig {
 pub struct StartClientConfig {
     /// The tunnels to open.
     pub tunnels: Vec<TunnelSpec>,
+
+    /// Whether to connect without opening any tunnels, just keeping the connection alive.
+    pub no_tunnels: bool,
 }
 
 impl StartClientConfig {
     pub const fn new() -> Self {
-        Self { tunnels: Vec::new() }
+        Self {
+            tunnels: Vec::new(),
+            no_tunnels: false,
+        }
     }
 }
 
 impl StartupArguments {
-    pub const fn new(verbose: bool, silent: bool, connect_method: ConnectMethod, startup_mode: StartupMode) -> Self {
+    pub const fn new(
+        verbose: bool,
+        silent: bool,
+        summary: bool,
+        socks_timeout: Option<Duration>,
+        connect_method: ConnectMethod,
+        startup_mode: StartupMode,
+    ) -> Self {
         Self {
             verbose,
             silent,
+            summary,
+            socks_timeout,
             connect_method,
             startup_mode,
         }
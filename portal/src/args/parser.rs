@@ -1,15 +1,16 @@
 use std::{
     fmt,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
 };
 
 use inlined::CompactVec;
 use portal_tunneler_proto::shared::TunnelSide;
 
 use super::{
-    parse_ip_addr_arg, parse_lane_count_arg, parse_port_number_arg, parse_socket_arg, parse_tunnel_spec_arg, ArgumentsRequest,
-    ConnectMethod, IpAddrErrorType, LaneCountErrorType, PortErrorType, PunchConfig, SocketErrorType, StartClientConfig, StartServerConfig,
-    StartupArguments, StartupMode, TunnelSpecErrorType, DEFAULT_PORT,
+    parse_ip_addr_arg, parse_lane_count_arg, parse_port_number_arg, parse_socket_arg, parse_timeout_arg, parse_tunnel_spec_arg,
+    ArgumentsRequest, ConnectMethod, IpAddrErrorType, LaneCountErrorType, PortErrorType, PunchConfig, SocketErrorType, StartClientConfig,
+    StartServerConfig, StartupArguments, StartupMode, TimeoutErrorType, TunnelSpecErrorType, DEFAULT_PORT,
 };
 
 #[derive(Debug, PartialEq, Eq)]
@@ -22,6 +23,8 @@ pub enum ArgumentsError {
     PortStart(PortErrorType),
     LocalTunnel(TunnelSpecErrorType),
     RemoteTunnel(TunnelSpecErrorType),
+    AcceptTimeout(TimeoutErrorType),
+    SocksTimeout(TimeoutErrorType),
     ServerCannotCreateTunnels,
     ConnectPunchFoundDirectArgument(String),
     ConnectDirectFoundPunchArgument(String),
@@ -29,6 +32,9 @@ pub enum ArgumentsError {
     ServerFoundClientArgument(String),
     MissingDestination,
     MissingTunnelSpecs,
+    NoTunnelsFoundTunnelSpecs,
+    NoPublicIpMissingMyIp,
+    MaxLaneCountBelowLaneCount,
 }
 
 impl fmt::Display for ArgumentsError {
@@ -42,6 +48,8 @@ impl fmt::Display for ArgumentsError {
             Self::PortStart(port_start_error) => port_start_error.fmt(f),
             Self::LocalTunnel(tunnel_spec_error) => tunnel_spec_error.fmt(f),
             Self::RemoteTunnel(tunnel_spec_error) => tunnel_spec_error.fmt(f),
+            Self::AcceptTimeout(timeout_error) => timeout_error.fmt(f),
+            Self::SocksTimeout(timeout_error) => timeout_error.fmt(f),
             Self::ServerCannotCreateTunnels => write!(f, "Cannot create tunnels in server mode, only clients can create tunnels"),
             Self::ConnectDirectFoundPunchArgument(arg) => write!(
                 f,
@@ -59,13 +67,19 @@ impl fmt::Display for ArgumentsError {
             }
             Self::MissingDestination => write!(f, "When running on client mode, a destination address must be specified"),
             Self::MissingTunnelSpecs => write!(f, "When running on client mode, you must specify at least one tunnel"),
+            Self::NoTunnelsFoundTunnelSpecs => write!(f, "Cannot specify tunnels when running with --no-tunnels"),
+            Self::NoPublicIpMissingMyIp => write!(f, "When running with --no-public-ip, you must specify your IP with --my-ip"),
+            Self::MaxLaneCountBelowLaneCount => write!(f, "The --max-lane-count must not be lower than the --lane-count"),
         }
     }
 }
 
 struct StartupArgumentsParser {
+    health_check: bool,
     verbose: bool,
     silent: bool,
+    summary: bool,
+    socks_timeout: Option<Duration>,
     connect_method: Option<ConnectMethod>,
     startup_mode: Option<StartupMode>,
 }
@@ -73,8 +87,11 @@ struct StartupArgumentsParser {
 impl StartupArgumentsParser {
     const fn new() -> Self {
         Self {
+            health_check: false,
             verbose: false,
             silent: false,
+            summary: false,
+            socks_timeout: None,
             connect_method: None,
             startup_mode: None,
         }
@@ -154,7 +171,7 @@ impl StartupArgumentsParser {
         Ok(())
     }
 
-    /*fn modify_startup_mode_server<F>(&mut self, arg: String, f: F) -> Result<(), ArgumentsError>
+    fn modify_startup_mode_server<F>(&mut self, arg: String, f: F) -> Result<(), ArgumentsError>
     where
         F: FnOnce(String, &mut StartServerConfig) -> Result<(), ArgumentsError>,
     {
@@ -169,12 +186,22 @@ impl StartupArgumentsParser {
         }
 
         Ok(())
-    }*/
+    }
 
     fn complete(self) -> Result<StartupArguments, ArgumentsError> {
         let startup_mode = self.startup_mode.unwrap_or_else(|| StartupMode::Server(StartServerConfig::new()));
         let mut connect_method = self.connect_method.unwrap_or_else(|| ConnectMethod::Direct(CompactVec::new()));
 
+        if let ConnectMethod::Punch(punch_config) = &connect_method {
+            if punch_config.no_public_ip && punch_config.my_ip.is_none() {
+                return Err(ArgumentsError::NoPublicIpMissingMyIp);
+            }
+
+            if punch_config.max_lane_count.is_some_and(|max| max < punch_config.lane_count) {
+                return Err(ArgumentsError::MaxLaneCountBelowLaneCount);
+            }
+        }
+
         if let ConnectMethod::Direct(sockets) = &mut connect_method {
             if sockets.is_empty() {
                 if startup_mode.is_client() {
@@ -187,16 +214,32 @@ impl StartupArgumentsParser {
         }
 
         if let StartupMode::Client(client_config) = &startup_mode {
-            if client_config.tunnels.is_empty() {
-                return Err(ArgumentsError::MissingTunnelSpecs);
+            match (client_config.no_tunnels, client_config.tunnels.is_empty()) {
+                (false, true) => return Err(ArgumentsError::MissingTunnelSpecs),
+                (true, false) => return Err(ArgumentsError::NoTunnelsFoundTunnelSpecs),
+                _ => {}
             }
         }
 
-        Ok(StartupArguments::new(self.verbose, self.silent, connect_method, startup_mode))
+        Ok(StartupArguments::new(
+            self.verbose,
+            self.silent,
+            self.summary,
+            self.socks_timeout,
+            connect_method,
+            startup_mode,
+        ))
     }
 }
 
-fn try_parse_general_argument(result: &mut StartupArgumentsParser, maybe_arg: &mut Option<String>) -> Result<bool, ArgumentsError> {
+fn try_parse_general_argument<F>(
+    result: &mut StartupArgumentsParser,
+    maybe_arg: &mut Option<String>,
+    get_next_arg: F,
+) -> Result<bool, ArgumentsError>
+where
+    F: FnOnce() -> Option<String>,
+{
     let arg = match maybe_arg.take() {
         Some(s) => s,
         None => return Ok(false),
@@ -206,6 +249,12 @@ fn try_parse_general_argument(result: &mut StartupArgumentsParser, maybe_arg: &m
         result.verbose = true;
     } else if arg.eq("-s") || arg.eq_ignore_ascii_case("--silent") {
         result.silent = true;
+    } else if arg.eq_ignore_ascii_case("--health-check") {
+        result.health_check = true;
+    } else if arg.eq_ignore_ascii_case("--summary") {
+        result.summary = true;
+    } else if arg.eq_ignore_ascii_case("--socks-timeout") {
+        result.socks_timeout = Some(parse_timeout_arg(arg, get_next_arg()).map_err(ArgumentsError::SocksTimeout)?);
     } else {
         *maybe_arg = Some(arg);
     }
@@ -228,6 +277,11 @@ where
 
     if arg.eq("--client") {
         result.ensure_startup_mode_client(arg)?;
+    } else if arg.eq_ignore_ascii_case("--no-tunnels") {
+        result.modify_startup_mode_client(arg, false, |_, client_config| {
+            client_config.no_tunnels = true;
+            Ok(())
+        })?;
     } else if arg.eq("--connect") {
         let arg = result.ensure_startup_mode_client(arg)?;
         result.modify_connect_method_direct(arg, |arg, sockets| {
@@ -260,6 +314,11 @@ where
         result.modify_connect_method_direct(arg, |arg, sockets| {
             parse_socket_arg(sockets, arg, get_next_arg(), DEFAULT_PORT).map_err(ArgumentsError::ListenError)
         })?;
+    } else if arg.eq_ignore_ascii_case("--accept-timeout") {
+        result.modify_startup_mode_server(arg, |arg, server_config| {
+            server_config.accept_timeout = Some(parse_timeout_arg(arg, get_next_arg()).map_err(ArgumentsError::AcceptTimeout)?);
+            Ok(())
+        })?;
     } else {
         *maybe_arg = Some(arg);
     }
@@ -287,11 +346,21 @@ where
             punch_config.my_ip = Some(parse_ip_addr_arg(arg, get_next_arg()).map_err(ArgumentsError::MyIpError)?);
             Ok(())
         })?;
+    } else if arg.eq_ignore_ascii_case("--no-public-ip") {
+        result.modify_connect_method_punch(arg, |_, punch_config| {
+            punch_config.no_public_ip = true;
+            Ok(())
+        })?;
     } else if arg.eq_ignore_ascii_case("--lane-count") {
         result.modify_connect_method_punch(arg, |arg, punch_config| {
             punch_config.lane_count = parse_lane_count_arg(arg, get_next_arg())?;
             Ok(())
         })?;
+    } else if arg.eq_ignore_ascii_case("--max-lane-count") {
+        result.modify_connect_method_punch(arg, |arg, punch_config| {
+            punch_config.max_lane_count = Some(parse_lane_count_arg(arg, get_next_arg())?);
+            Ok(())
+        })?;
     } else if arg.eq_ignore_ascii_case("--port-start") {
         result.modify_connect_method_punch(arg, |arg, punch_config| {
             punch_config.port_start = Some(parse_port_number_arg(arg, get_next_arg()).map_err(ArgumentsError::PortStart)?);
@@ -371,7 +440,7 @@ where
         }
 
         let mut maybe_arg = Some(arg);
-        let _ = !try_parse_general_argument(&mut result, &mut maybe_arg)?
+        let _ = !try_parse_general_argument(&mut result, &mut maybe_arg, || args.next())?
             && !try_parse_client_argument(&mut result, &mut maybe_arg, || args.next())?
             && !try_parse_server_argument(&mut result, &mut maybe_arg, || args.next())?
             && !try_parse_punch_argument(&mut result, &mut maybe_arg, || args.next())?
@@ -382,6 +451,209 @@ where
         }
     }
 
+    let health_check = result.health_check;
     let result = result.complete()?;
-    Ok(ArgumentsRequest::Run(result))
+    match health_check {
+        true => Ok(ArgumentsRequest::HealthCheck(result)),
+        false => Ok(ArgumentsRequest::Run(result)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    };
+
+    use super::{parse_arguments, ArgumentsError, ArgumentsRequest, ConnectMethod, StartupMode, TimeoutErrorType};
+
+    fn parse(args: &[&str]) -> Result<ArgumentsRequest, ArgumentsError> {
+        parse_arguments(std::iter::once("portal").chain(args.iter().copied()).map(String::from))
+    }
+
+    #[test]
+    fn test_no_tunnels() {
+        let result = parse(&["--connect", "127.0.0.1", "--no-tunnels"]);
+        let startup_args = match result {
+            Ok(ArgumentsRequest::Run(startup_args)) => startup_args,
+            other => panic!("Expected startup arguments, got {other:?}"),
+        };
+
+        match startup_args.startup_mode {
+            StartupMode::Client(client_config) => {
+                assert!(client_config.no_tunnels);
+                assert!(client_config.tunnels.is_empty());
+            }
+            StartupMode::Server(_) => panic!("Expected client mode"),
+        }
+    }
+
+    #[test]
+    fn test_missing_tunnel_specs() {
+        assert_eq!(parse(&["--connect", "127.0.0.1"]), Err(ArgumentsError::MissingTunnelSpecs));
+    }
+
+    #[test]
+    fn test_no_tunnels_with_tunnel_specs() {
+        let result = parse(&["--connect", "127.0.0.1", "--no-tunnels", "-L4444:localhost:5555"]);
+        assert_eq!(result, Err(ArgumentsError::NoTunnelsFoundTunnelSpecs));
+    }
+
+    #[test]
+    fn test_no_tunnels_in_server_mode() {
+        let result = parse(&["--listen", "127.0.0.1", "--no-tunnels"]);
+        assert_eq!(result, Err(ArgumentsError::ServerFoundClientArgument(String::from("--no-tunnels"))));
+    }
+
+    #[test]
+    fn test_no_public_ip_with_my_ip() {
+        let result = parse(&["--punch", "--no-public-ip", "--my-ip", "1.2.3.4"]);
+        let startup_args = match result {
+            Ok(ArgumentsRequest::Run(startup_args)) => startup_args,
+            other => panic!("Expected startup arguments, got {other:?}"),
+        };
+
+        match startup_args.connect_method {
+            ConnectMethod::Punch(punch_config) => {
+                assert!(punch_config.no_public_ip);
+                assert_eq!(punch_config.my_ip, Some(IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4))));
+            }
+            ConnectMethod::Direct(_) => panic!("Expected punch connect method"),
+        }
+    }
+
+    #[test]
+    fn test_no_public_ip_missing_my_ip() {
+        assert_eq!(parse(&["--punch", "--no-public-ip"]), Err(ArgumentsError::NoPublicIpMissingMyIp));
+    }
+
+    #[test]
+    fn test_no_public_ip_with_direct_connection() {
+        let result = parse(&["--listen", "127.0.0.1", "--no-public-ip"]);
+        assert_eq!(
+            result,
+            Err(ArgumentsError::ConnectDirectFoundPunchArgument(String::from("--no-public-ip")))
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        for args in [
+            &["--summary", "--listen", "127.0.0.1"][..],
+            &["--connect", "127.0.0.1", "--no-tunnels", "--summary"],
+        ] {
+            match parse(args) {
+                Ok(ArgumentsRequest::Run(startup_args)) => assert!(startup_args.summary),
+                other => panic!("Expected startup arguments, got {other:?}"),
+            }
+        }
+
+        match parse(&["--listen", "127.0.0.1"]) {
+            Ok(ArgumentsRequest::Run(startup_args)) => assert!(!startup_args.summary),
+            other => panic!("Expected startup arguments, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_max_lane_count() {
+        let result = parse(&["--server", "--lane-count", "4", "--max-lane-count", "32"]);
+        let startup_args = match result {
+            Ok(ArgumentsRequest::Run(startup_args)) => startup_args,
+            other => panic!("Expected startup arguments, got {other:?}"),
+        };
+
+        match startup_args.connect_method {
+            ConnectMethod::Punch(punch_config) => {
+                assert_eq!(punch_config.lane_count.get(), 4);
+                assert_eq!(punch_config.max_lane_count.map(|c| c.get()), Some(32));
+            }
+            ConnectMethod::Direct(_) => panic!("Expected punch connect method"),
+        }
+    }
+
+    #[test]
+    fn test_max_lane_count_below_lane_count() {
+        let result = parse(&["--server", "--lane-count", "8", "--max-lane-count", "4"]);
+        assert_eq!(result, Err(ArgumentsError::MaxLaneCountBelowLaneCount));
+    }
+
+    #[test]
+    fn test_accept_timeout() {
+        let result = parse(&["--punch", "--accept-timeout", "90"]);
+        let startup_args = match result {
+            Ok(ArgumentsRequest::Run(startup_args)) => startup_args,
+            other => panic!("Expected startup arguments, got {other:?}"),
+        };
+
+        match startup_args.startup_mode {
+            StartupMode::Server(server_config) => assert_eq!(server_config.accept_timeout, Some(Duration::from_secs(90))),
+            StartupMode::Client(_) => panic!("Expected server mode"),
+        }
+    }
+
+    #[test]
+    fn test_accept_timeout_errors() {
+        assert_eq!(
+            parse(&["--accept-timeout", "0"]),
+            Err(ArgumentsError::AcceptTimeout(TimeoutErrorType::MustBeGreaterThanZero(
+                String::from("--accept-timeout"),
+                String::from("0")
+            )))
+        );
+        assert_eq!(
+            parse(&["--accept-timeout"]),
+            Err(ArgumentsError::AcceptTimeout(TimeoutErrorType::UnexpectedEnd(String::from(
+                "--accept-timeout"
+            ))))
+        );
+        assert_eq!(
+            parse(&["--connect", "127.0.0.1", "--accept-timeout", "5"]),
+            Err(ArgumentsError::ClientFoundServerArgument(String::from("--accept-timeout")))
+        );
+    }
+
+    #[test]
+    fn test_socks_timeout() {
+        for args in [
+            &["--socks-timeout", "15", "--listen", "127.0.0.1"][..],
+            &["--connect", "127.0.0.1", "--no-tunnels", "--socks-timeout", "15"],
+        ] {
+            match parse(args) {
+                Ok(ArgumentsRequest::Run(startup_args)) => assert_eq!(startup_args.socks_timeout, Some(Duration::from_secs(15))),
+                other => panic!("Expected startup arguments, got {other:?}"),
+            }
+        }
+
+        match parse(&["--listen", "127.0.0.1"]) {
+            Ok(ArgumentsRequest::Run(startup_args)) => assert_eq!(startup_args.socks_timeout, None),
+            other => panic!("Expected startup arguments, got {other:?}"),
+        }
+
+        assert_eq!(
+            parse(&["--socks-timeout", "soon"]),
+            Err(ArgumentsError::SocksTimeout(TimeoutErrorType::InvalidValue(
+                String::from("--socks-timeout"),
+                String::from("soon")
+            )))
+        );
+    }
+
+    #[test]
+    fn test_health_check() {
+        match parse(&["--health-check", "--listen", "127.0.0.1"]) {
+            Ok(ArgumentsRequest::HealthCheck(startup_args)) => assert!(startup_args.startup_mode.is_server()),
+            other => panic!("Expected a health check request, got {other:?}"),
+        }
+
+        match parse(&["--connect", "127.0.0.1", "--no-tunnels", "--health-check"]) {
+            Ok(ArgumentsRequest::HealthCheck(startup_args)) => assert!(startup_args.startup_mode.is_client()),
+            other => panic!("Expected a health check request, got {other:?}"),
+        }
+
+        assert_eq!(
+            parse(&["--health-check", "--connect", "127.0.0.1"]),
+            Err(ArgumentsError::MissingTunnelSpecs)
+        );
+    }
 }
@@ -0,0 +1,32 @@
+use std::{fmt, time::Duration};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeoutErrorType {
+    UnexpectedEnd(String),
+    MustBeGreaterThanZero(String, String),
+    InvalidValue(String, String),
+}
+
+impl fmt::Display for TimeoutErrorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEnd(arg) => write!(f, "Expected amount of seconds after {arg}"),
+            Self::MustBeGreaterThanZero(arg, arg2) => write!(f, "Timeout must be greater than 0 after {arg}: {arg2}"),
+            Self::InvalidValue(arg, arg2) => write!(f, "Invalid timeout value after {arg}: {arg2}"),
+        }
+    }
+}
+
+/// Parses a timeout argument, specified as a nonzero integer amount of seconds.
+pub(super) fn parse_timeout_arg(arg: String, maybe_arg2: Option<String>) -> Result<Duration, TimeoutErrorType> {
+    let arg2 = match maybe_arg2 {
+        Some(arg2) => arg2,
+        None => return Err(TimeoutErrorType::UnexpectedEnd(arg)),
+    };
+
+    match arg2.parse::<u64>() {
+        Ok(0) => Err(TimeoutErrorType::MustBeGreaterThanZero(arg, arg2)),
+        Ok(seconds) => Ok(Duration::from_secs(seconds)),
+        Err(_) => Err(TimeoutErrorType::InvalidValue(arg, arg2)),
+    }
+}
@@ -31,6 +31,8 @@ use portal_tunneler_proto::{
 use quinn::{RecvStream, SendStream};
 use tokio::try_join;
 
+use crate::utils::SessionStats;
+
 struct CreateRemoteTunnelsState {
     client: Rc<ClientState>,
     inner: RefCell<CreateRemoteTunnelsStateInner>,
@@ -101,12 +103,18 @@ async fn receive_tunnel_results(operation_state: &CreateRemoteTunnelsState, recv
     }
 }
 
-pub async fn start_remote_tunnels(client: Rc<ClientState>, remote_tunnel_specs: Vec<TunnelSpec>) -> io::Result<()> {
+/// Opens the control stream and starts the given remote tunnels on the server. The server closes
+/// its side once every request has been answered, so the stream is counted as open in `stats`
+/// until then, rather than for the whole session.
+pub async fn start_remote_tunnels(client: Rc<ClientState>, stats: &SessionStats, remote_tunnel_specs: Vec<TunnelSpec>) -> io::Result<()> {
     if remote_tunnel_specs.is_empty() {
         return Ok(());
     }
 
+    // Declared before the stream halves so it's dropped after them, keeping the stream counted until it's closed.
+    let _stream_guard;
     let (mut send_stream, mut recv_stream) = client.connection().open_bi().await?;
+    _stream_guard = stats.track_stream();
     ClientStreamRequest::StartRemoteTunnels.write(&mut send_stream).await?;
 
     let operation_state = CreateRemoteTunnelsState::new(client, remote_tunnel_specs);
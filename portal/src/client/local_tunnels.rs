@@ -1,4 +1,4 @@
-use std::{io, rc::Rc};
+use std::{io, rc::Rc, time::Duration};
 
 use portal_tunneler_proto::{
     client::ClientState,
@@ -11,9 +11,18 @@ use tokio::{
     try_join,
 };
 
-use crate::socks;
+use crate::{
+    socks,
+    utils::{copy_counted, SessionStats},
+};
 
-pub async fn handle_local_tunnel_listening(client: Rc<ClientState>, listener: TcpListener, spec: Rc<TunnelSpec>) {
+pub async fn handle_local_tunnel_listening(
+    client: Rc<ClientState>,
+    stats: Rc<SessionStats>,
+    listener: TcpListener,
+    spec: Rc<TunnelSpec>,
+    socks_timeout: Option<Duration>,
+) {
     loop {
         let (tcp_stream, from) = match listener.accept().await {
             Ok(t) => t,
@@ -31,8 +40,9 @@ pub async fn handle_local_tunnel_listening(client: Rc<ClientState>, listener: Tc
 
         let client = Rc::clone(&client);
         let spec = Rc::clone(&spec);
+        let stats = Rc::clone(&stats);
         tokio::task::spawn_local(async move {
-            match handle_local_tunnel(client, tcp_stream, spec).await {
+            match handle_local_tunnel(client, &stats, tcp_stream, spec, socks_timeout).await {
                 Ok(()) => {}
                 Err(error) => println!("Local tunnel task finished with error: {error}"),
             }
@@ -40,13 +50,19 @@ pub async fn handle_local_tunnel_listening(client: Rc<ClientState>, listener: Tc
     }
 }
 
-pub async fn handle_local_tunnel(client: Rc<ClientState>, mut tcp_stream: TcpStream, spec: Rc<TunnelSpec>) -> io::Result<()> {
+pub async fn handle_local_tunnel(
+    client: Rc<ClientState>,
+    stats: &SessionStats,
+    mut tcp_stream: TcpStream,
+    spec: Rc<TunnelSpec>,
+    socks_timeout: Option<Duration>,
+) -> io::Result<()> {
     let (mut read_half, mut write_half) = tcp_stream.split();
 
     let maybe_socks_target;
     let (maybe_socks_version, target) = match &spec.target {
         TunnelTarget::Socks => {
-            let request_result = socks::read_request(&mut read_half, &mut write_half).await;
+            let request_result = socks::read_request(&mut read_half, &mut write_half, socks_timeout).await;
 
             if let Err(socks_error) = &request_result {
                 println!("Socks error: {socks_error}");
@@ -60,7 +76,10 @@ pub async fn handle_local_tunnel(client: Rc<ClientState>, mut tcp_stream: TcpStr
         TunnelTarget::Address(address) => (None, address.as_ref()),
     };
 
+    // Declared before the stream halves so it's dropped after them, keeping the stream counted until it's closed.
+    let _stream_guard;
     let (mut send_stream, mut recv_stream) = client.connection().open_bi().await?;
+    _stream_guard = stats.track_stream();
     ClientStreamRequest::OpenLocalTunnelConnection.write(&mut send_stream).await?;
 
     let request = OpenLocalConnectionRequestRef::new(target);
@@ -79,9 +98,11 @@ pub async fn handle_local_tunnel(client: Rc<ClientState>, mut tcp_stream: TcpStr
 
     println!("Local tunnel connected through server (remote socket bound at {bind_address})");
 
+    let tunnel_index = Some(spec.index);
+    stats.record_connection(tunnel_index);
     let result = try_join!(
-        tokio::io::copy(&mut read_half, &mut send_stream),
-        tokio::io::copy(&mut recv_stream, &mut write_half),
+        copy_counted(&mut read_half, &mut send_stream, |bytes| stats.record_sent(tunnel_index, bytes)),
+        copy_counted(&mut recv_stream, &mut write_half, |bytes| stats.record_received(tunnel_index, bytes)),
     );
 
     match result {
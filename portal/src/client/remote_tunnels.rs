@@ -11,10 +11,11 @@ use portal_tunneler_proto::{
 use quinn::{RecvStream, SendStream};
 use tokio::try_join;
 
-use crate::utils::{bind_connect, UNSPECIFIED_SOCKADDR_V4};
+use crate::utils::{bind_connect, copy_counted, SessionStats, UNSPECIFIED_SOCKADDR_V4};
 
 pub async fn handle_incoming_bi_stream(
     client: Rc<ClientState>,
+    stats: Rc<SessionStats>,
     mut send_stream: SendStream,
     mut recv_stream: RecvStream,
 ) -> io::Result<()> {
@@ -73,9 +74,11 @@ pub async fn handle_incoming_bi_stream(
 
     let mut tcp_stream = tcp_stream_result?;
     let (mut read_half, mut write_half) = tcp_stream.split();
+    let tunnel_index = Some(spec.index);
+    stats.record_connection(tunnel_index);
     let result = try_join!(
-        tokio::io::copy(&mut read_half, &mut send_stream),
-        tokio::io::copy(&mut recv_stream, &mut write_half),
+        copy_counted(&mut read_half, &mut send_stream, |bytes| stats.record_sent(tunnel_index, bytes)),
+        copy_counted(&mut recv_stream, &mut write_half, |bytes| stats.record_received(tunnel_index, bytes)),
     );
 
     match result {
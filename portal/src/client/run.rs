@@ -1,4 +1,4 @@
-use std::{io, rc::Rc};
+use std::{io, rc::Rc, time::Duration};
 
 use portal_tunneler_proto::{client::ClientState, shared::TunnelSide};
 use quinn::{Connection, ConnectionError};
@@ -9,24 +9,35 @@ use crate::{
         create_remote_tunnels::start_remote_tunnels, local_tunnels::handle_local_tunnel_listening,
         remote_tunnels::handle_incoming_bi_stream,
     },
-    utils::bind_listeners,
+    utils::{bind_listeners, SessionStats, SessionSummary},
 };
 
-pub async fn run_client(connection: Connection, config: StartClientConfig) -> io::Result<()> {
+pub async fn run_client(
+    connection: Connection,
+    config: StartClientConfig,
+    summary: bool,
+    socks_timeout: Option<Duration>,
+) -> io::Result<SessionSummary> {
     println!("Client connected to {}", connection.remote_address());
 
     let client = Rc::new(ClientState::new(connection));
+    let stats = Rc::new(SessionStats::new());
     let mut tunnels = config.tunnels;
 
-    for spec in tunnels.extract_if(|spec| spec.side == TunnelSide::Local) {
+    if config.no_tunnels {
+        println!("Running with no tunnels, the connection will be kept alive until closed");
+    }
+
+    for spec in tunnels.extract_if(.., |spec| spec.side == TunnelSide::Local) {
         match bind_listeners(spec.listen_address.as_ref()).await {
             Ok(listeners) => {
                 let spec = Rc::new(spec);
                 for listener in listeners {
                     let client = Rc::clone(&client);
                     let spec = Rc::clone(&spec);
+                    let stats = Rc::clone(&stats);
                     tokio::task::spawn_local(async move {
-                        handle_local_tunnel_listening(client, listener, spec).await;
+                        handle_local_tunnel_listening(client, stats, listener, spec, socks_timeout).await;
                     });
                 }
             }
@@ -36,7 +47,7 @@ pub async fn run_client(connection: Connection, config: StartClientConfig) -> io
         }
     }
 
-    start_remote_tunnels(Rc::clone(&client), tunnels).await?;
+    start_remote_tunnels(Rc::clone(&client), &stats, tunnels).await?;
 
     let result_error = loop {
         let (send_stream, recv_stream) = match client.connection().accept_bi().await {
@@ -45,8 +56,10 @@ pub async fn run_client(connection: Connection, config: StartClientConfig) -> io
         };
 
         let client = Rc::clone(&client);
+        let stats = Rc::clone(&stats);
         tokio::task::spawn_local(async move {
-            match handle_incoming_bi_stream(client, send_stream, recv_stream).await {
+            let _stream_guard = stats.track_stream();
+            match handle_incoming_bi_stream(client, Rc::clone(&stats), send_stream, recv_stream).await {
                 Ok(()) => {}
                 Err(error) => println!("Handle incoming bidi stream task finished with error: {error}"),
             }
@@ -59,5 +72,205 @@ pub async fn run_client(connection: Connection, config: StartClientConfig) -> io
         error => eprintln!("The connection closed unexpectedly: {error}"),
     };
 
-    Ok(())
+    let session_summary = stats.summary();
+    if summary {
+        println!("{session_summary}");
+    }
+
+    Ok(session_summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr, TcpListener as StdTcpListener, UdpSocket},
+        time::Duration,
+    };
+
+    use portal_tunneler_proto::shared::{AddressOrDomainname, TunnelSide, TunnelSpec, TunnelTarget};
+    use quinn::{Endpoint, VarInt};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        task::LocalSet,
+    };
+
+    use crate::{
+        args::StartClientConfig,
+        endpoint::{make_endpoint, EndpointSocketSource, MAX_IDLE_TIMEOUT_MILLIS},
+        server::run::{handle_connection, run_server},
+        utils::TunnelStats,
+    };
+
+    use super::run_client;
+
+    fn localhost(port: u16) -> SocketAddr {
+        SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port)
+    }
+
+    fn make_local_endpoint(is_client: bool, is_server: bool) -> Endpoint {
+        let socket = UdpSocket::bind(localhost(0)).unwrap();
+        make_endpoint(EndpointSocketSource::Simple(socket), is_client, is_server).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_no_tunnels_keeps_connection_open() {
+        let server = make_local_endpoint(false, true);
+        let server_address = server.local_addr().unwrap();
+        let client = make_local_endpoint(true, false);
+
+        LocalSet::new()
+            .run_until(async move {
+                let server_task = tokio::task::spawn_local(run_server(server, None, None, false, None, None));
+
+                let connection = client
+                    .connect(server_address, &server_address.ip().to_string())
+                    .unwrap()
+                    .await
+                    .unwrap();
+                let mut config = StartClientConfig::new();
+                config.no_tunnels = true;
+                let client_task = tokio::task::spawn_local(run_client(connection.clone(), config, false, None));
+
+                // Wait for longer than the idle timeout, so the connection would have closed if
+                // nothing kept it alive.
+                tokio::time::sleep(Duration::from_millis(MAX_IDLE_TIMEOUT_MILLIS as u64 + 1000)).await;
+                assert!(!client_task.is_finished(), "The client should keep running with no tunnels");
+                assert!(connection.close_reason().is_none(), "The connection should stay open");
+
+                connection.close(VarInt::from_u32(0), b"");
+                let result = tokio::time::timeout(Duration::from_secs(5), client_task).await;
+                assert!(
+                    matches!(result, Ok(Ok(Ok(_)))),
+                    "The client should finish once the connection is closed"
+                );
+                server_task.abort();
+            })
+            .await;
+    }
+
+    /// Connects to a tunnel at the specified address, retrying until it's listening, then sends the
+    /// specified message and returns the first `reply_len` bytes received.
+    async fn exchange_through_tunnel(address: SocketAddr, message: &[u8], reply_len: usize) -> Vec<u8> {
+        let mut stream = loop {
+            match TcpStream::connect(address).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        stream.write_all(message).await.unwrap();
+        let mut received = vec![0; reply_len];
+        tokio::time::timeout(Duration::from_secs(5), stream.read_exact(&mut received))
+            .await
+            .expect("The tunnel should forward the reply")
+            .unwrap();
+        received
+    }
+
+    #[tokio::test]
+    async fn test_session_summary() {
+        const MESSAGE: &[u8] = b"Hello through the tunnel!";
+
+        let server = make_local_endpoint(false, true);
+        let server_address = server.local_addr().unwrap();
+        let client = make_local_endpoint(true, false);
+
+        let target = TcpListener::bind(localhost(0)).await.unwrap();
+        let target_address = target.local_addr().unwrap();
+        let local_tunnel_address = StdTcpListener::bind(localhost(0)).unwrap().local_addr().unwrap();
+        let remote_tunnel_address = StdTcpListener::bind(localhost(0)).unwrap().local_addr().unwrap();
+
+        let mut config = StartClientConfig::new();
+        for (index, side, listen_address) in [
+            (0, TunnelSide::Local, local_tunnel_address),
+            (1, TunnelSide::Remote, remote_tunnel_address),
+        ] {
+            config.tunnels.push(TunnelSpec {
+                index,
+                side,
+                target: TunnelTarget::Address(AddressOrDomainname::Address(target_address)),
+                listen_address: AddressOrDomainname::Address(listen_address),
+            });
+        }
+
+        LocalSet::new()
+            .run_until(async move {
+                // The target answers with the message it receives repeated twice, then closes.
+                tokio::task::spawn_local(async move {
+                    loop {
+                        let (mut stream, _) = target.accept().await.unwrap();
+                        let mut buf = vec![0; MESSAGE.len()];
+                        stream.read_exact(&mut buf).await.unwrap();
+                        stream.write_all(&buf).await.unwrap();
+                        stream.write_all(&buf).await.unwrap();
+                    }
+                });
+
+                let server_task = tokio::task::spawn_local(async move {
+                    let incoming_connection = server.accept().await.unwrap();
+                    handle_connection(incoming_connection, None, None, true, None).await
+                });
+                let connection = client
+                    .connect(server_address, &server_address.ip().to_string())
+                    .unwrap()
+                    .await
+                    .unwrap();
+                let client_task = tokio::task::spawn_local(run_client(connection.clone(), config, true, None));
+
+                let expected = [MESSAGE, MESSAGE].concat();
+                assert_eq!(exchange_through_tunnel(local_tunnel_address, MESSAGE, expected.len()).await, expected);
+                assert_eq!(exchange_through_tunnel(remote_tunnel_address, MESSAGE, expected.len()).await, expected);
+
+                // Give both ends of the tunnels a moment to record the copied bytes.
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                connection.close(VarInt::from_u32(0), b"");
+                let client_summary = client_task.await.unwrap().unwrap();
+                let server_summary = tokio::time::timeout(Duration::from_secs(5), server_task)
+                    .await
+                    .expect("The server should finish once the connection is closed")
+                    .unwrap()
+                    .expect("The server should have accepted the connection");
+
+                // Each tunneled connection sends the message one way and twice the other way.
+                let len = MESSAGE.len() as u64;
+                let forward = TunnelStats {
+                    connections: 1,
+                    bytes_sent: len,
+                    bytes_received: len * 2,
+                };
+                let backward = TunnelStats {
+                    connections: 1,
+                    bytes_sent: len * 2,
+                    bytes_received: len,
+                };
+                let totals = TunnelStats {
+                    connections: 2,
+                    bytes_sent: len * 3,
+                    bytes_received: len * 3,
+                };
+
+                // The client sees the local tunnel's forward direction and the remote tunnel's
+                // backward direction, listed by their indices.
+                assert_eq!(client_summary.totals, totals);
+                assert_eq!(client_summary.tunnels, [(0, forward), (1, backward)]);
+                assert!(client_summary.to_string().contains("Connections handled: 2"));
+
+                // Tunneled connections stay open until the connection closes, so both streams were
+                // open at the same time.
+                assert_eq!(client_summary.max_concurrent_streams, 2);
+
+                // The server sees the opposite directions. Local tunnel connections don't identify
+                // their tunnel, so only the remote tunnel is listed, by its remote tunnel ID.
+                assert_eq!(server_summary.totals, totals);
+                assert_eq!(server_summary.tunnels, [(0, forward)]);
+                let server_report = server_summary.to_string();
+                assert!(server_report.contains("Connections handled: 2"));
+                assert!(server_report.contains(&format!(
+                    "Remote tunnel ID 0: 1 connections, {len} bytes sent, {} bytes received",
+                    len * 2
+                )));
+            })
+            .await;
+    }
 }
@@ -5,11 +5,15 @@ use std::{
     num::NonZeroU16,
     pin::Pin,
     task::Poll,
+    time::Duration,
 };
 
 use inlined::CompactVec;
 use quinn::{Connection, Endpoint};
-use tokio::io::{stdin, AsyncBufReadExt, BufReader};
+use tokio::{
+    io::{stdin, AsyncBufReadExt, BufReader},
+    net::UdpSocket,
+};
 
 use crate::{
     args::PunchConfig,
@@ -24,14 +28,108 @@ use crate::{
     utils::{get_current_timestamp, UNSPECIFIED_SOCKADDR_V4, UNSPECIFIED_SOCKADDR_V6},
 };
 
-pub async fn punch(punch_config: PunchConfig, is_server: bool) -> io::Result<PunchConnectResult> {
-    let port_start = punch_config.port_start.map(|p| p.get()).unwrap_or(0);
+/// The factor by which the lane count is multiplied when retrying a failed punch.
+const LANE_COUNT_GROWTH_FACTOR: NonZeroU16 = unsafe { NonZeroU16::new_unchecked(2) };
+
+/// Punches a connection with a friend, exchanging connection codes through stdin and stdout.
+///
+/// If `max_lane_count` is set and an attempt fails, it is retried with more lanes. Only the lanes
+/// in both connection codes are punched though, so a retry only uses more lanes if the friend
+/// retries with more lanes too (e.g. by also setting `--max-lane-count`).
+pub async fn punch(punch_config: PunchConfig, is_server: bool, accept_timeout: Option<Duration>) -> io::Result<PunchConnectResult> {
     let lane_count = punch_config.lane_count;
+    let sockets = bind_punch_sockets(punch_config.port_start, lane_count)?;
+
+    let public_ip = resolve_public_ip(punch_config.my_ip, punch_config.no_public_ip, get_public_ipv4).await?;
 
+    retry_punch(sockets, punch_config.port_start, lane_count, punch_config.max_lane_count, |sockets| {
+        punch_attempt(sockets, IpAddr::V4(public_ip), is_server, accept_timeout)
+    })
+    .await
+}
+
+/// Runs `attempt` with the given sockets, binding more sockets and running it again for as long as
+/// [`retry_lane_count`] allows it.
+async fn retry_punch<T, F, Fut>(
+    mut sockets: Vec<UdpSocket>,
+    port_start: Option<NonZeroU16>,
+    mut lane_count: NonZeroU16,
+    max_lane_count: Option<NonZeroU16>,
+    mut attempt: F,
+) -> io::Result<T>
+where
+    F: FnMut(Vec<UdpSocket>) -> Fut,
+    Fut: Future<Output = io::Result<T>>,
+{
+    loop {
+        let error = match attempt(sockets).await {
+            Ok(result) => return Ok(result),
+            Err(error) => error,
+        };
+
+        lane_count = match retry_lane_count(&error, lane_count, max_lane_count) {
+            Some(count) => count,
+            None => return Err(error),
+        };
+
+        println!("Punching failed: {error}");
+        println!("Retrying with {lane_count} lanes. Both you and your friend will need to exchange new connection codes.");
+        sockets = bind_punch_sockets(port_start, lane_count)?;
+    }
+}
+
+/// Gets our public IP address, which is `my_ip` if one was given or otherwise the one found by
+/// calling `discover`. If `no_public_ip` is set, `discover` is never called and an error is returned
+/// if `my_ip` is `None` (although the argument parser already ensures `my_ip` is set in that case).
+async fn resolve_public_ip<F, Fut>(my_ip: Option<IpAddr>, no_public_ip: bool, discover: F) -> io::Result<Ipv4Addr>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = io::Result<Ipv4Addr>>,
+{
+    match my_ip {
+        Some(IpAddr::V4(ipv4)) => Ok(ipv4),
+        Some(IpAddr::V6(_)) => panic!("Support for IPv6 is not implemented yet"),
+        None if no_public_ip => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Public IP discovery is disabled, but no IP address was specified",
+        )),
+        None => {
+            print!("Finding your public IP address...");
+            std::io::stdout().flush()?;
+            let public_ip = discover().await?;
+            println!(" {public_ip}");
+            Ok(public_ip)
+        }
+    }
+}
+
+/// Gets the lane count to retry punching with after a punch attempt failed with the given error, or
+/// `None` if the error should be returned instead.
+///
+/// Only attempts where no lane got through are retried, and only if adaptive lane counts are
+/// enabled. Retrying with a wider range of ports helps with NATs that remap ports, where a wider
+/// range is more likely to include the mapped port. Any other error, such as an invalid connection
+/// code or a client-server mismatch, wouldn't be fixed by retrying.
+fn retry_lane_count(error: &Error, lane_count: NonZeroU16, max_lane_count: Option<NonZeroU16>) -> Option<NonZeroU16> {
+    match (puncher::PunchFailure::from_error(error), max_lane_count) {
+        (Some(_), Some(max_lane_count)) => next_lane_count(lane_count, max_lane_count),
+        _ => None,
+    }
+}
+
+/// Gets the lane count to use for retrying after a failed punch attempt, which doubles the lane
+/// count up to `max_lane_count`. Returns `None` if the lane count is already at the maximum.
+fn next_lane_count(lane_count: NonZeroU16, max_lane_count: NonZeroU16) -> Option<NonZeroU16> {
+    match lane_count < max_lane_count {
+        true => Some(lane_count.saturating_mul(LANE_COUNT_GROWTH_FACTOR).min(max_lane_count)),
+        false => None,
+    }
+}
+
+pub fn bind_punch_sockets(port_start: Option<NonZeroU16>, lane_count: NonZeroU16) -> io::Result<Vec<UdpSocket>> {
     print!("Binding sockets...");
     std::io::stdout().flush()?;
-    let sockets = bind_sockets(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port_start), lane_count)?;
-    let port_start = sockets[0].local_addr().unwrap().port();
+    let sockets = bind_punch_sockets_quietly(port_start, lane_count)?;
 
     if sockets.len() == 1 {
         println!(" Done, bound a single socket at {}", sockets.first().unwrap().local_addr().unwrap());
@@ -41,26 +139,34 @@ pub async fn punch(punch_config: PunchConfig, is_server: bool) -> io::Result<Pun
         println!(" Done, bound {} sockets from {} to {}", sockets.len(), first_addr, last_addr);
     }
 
-    print!("Finding your public IP address...");
-    std::io::stdout().flush()?;
-    let public_ip = match punch_config.my_ip {
-        Some(ip) => match ip {
-            IpAddr::V4(ipv4) => ipv4,
-            IpAddr::V6(_) => panic!("Support for IPv6 is not implemented yet"),
-        },
-        None => get_public_ipv4().await?,
-    };
-    println!(" {public_ip}");
+    Ok(sockets)
+}
+
+/// Binds the sockets for hole punching like [`bind_punch_sockets`], but without printing anything.
+pub fn bind_punch_sockets_quietly(port_start: Option<NonZeroU16>, lane_count: NonZeroU16) -> io::Result<Vec<UdpSocket>> {
+    let port_start = port_start.map(|p| p.get()).unwrap_or(0);
+    bind_sockets(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), port_start), lane_count)
+}
+
+async fn punch_attempt(
+    sockets: Vec<UdpSocket>,
+    public_ip: IpAddr,
+    is_server: bool,
+    accept_timeout: Option<Duration>,
+) -> io::Result<PunchConnectResult> {
+    let port_start = sockets[0].local_addr().unwrap().port();
+    let lane_count = NonZeroU16::new(sockets.len() as u16).unwrap();
 
-    let connection_code = ConnectionCode::new(IpAddr::V4(public_ip), port_start, lane_count);
+    let connection_code = ConnectionCode::new(public_ip, port_start, lane_count);
     println!("Your connection code is: {}", connection_code.serialize_to_string());
+    println!("Or, as words: {}", connection_code.serialize_to_words());
 
     print!("Enter your friend's connection code: ");
     std::io::stdout().flush()?;
     let mut s = String::with_capacity(CONNECTION_STRING_MAX_LENGTH_CHARS + 2);
     let mut stdin = BufReader::with_capacity(1024, stdin());
     stdin.read_line(&mut s).await?;
-    let destination_code = ConnectionCode::deserialize_from_str(s.trim()).map_err(|e| {
+    let destination_code = ConnectionCode::parse(&s).map_err(|e| {
         let message = format!("Invalid error code: {e:?}");
         Error::new(ErrorKind::InvalidData, message)
     })?;
@@ -75,13 +181,18 @@ pub async fn punch(punch_config: PunchConfig, is_server: bool) -> io::Result<Pun
             "Local lane count: {}, Remote lane count: {}",
             connection_code.lane_count, destination_code.lane_count
         );
+
+        if connection_code.lane_count > destination_code.lane_count {
+            println!("Your extra lanes will only be punched once your friend also uses more lanes.");
+        }
     }
 
     let remote_port_start = NonZeroU16::new(destination_code.port_start).unwrap();
     let lane_count = connection_code.lane_count.min(destination_code.lane_count);
 
     println!("Punching!");
-    puncher::punch_connection(is_server, sockets, destination_code.address, remote_port_start, lane_count).await
+    let remote_address = destination_code.address;
+    puncher::punch_connection(is_server, sockets, remote_address, remote_port_start, lane_count, accept_timeout).await
 }
 
 pub async fn connect_client(
@@ -231,3 +342,114 @@ pub async fn connect_server(
         false => Ok(endpoints),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Error, ErrorKind},
+        net::{IpAddr, Ipv4Addr},
+        num::NonZeroU16,
+    };
+
+    use crate::puncher::PunchFailure;
+
+    use super::{bind_punch_sockets, next_lane_count, resolve_public_ip, retry_lane_count, retry_punch};
+
+    fn nz(value: u16) -> NonZeroU16 {
+        NonZeroU16::new(value).unwrap()
+    }
+
+    #[test]
+    fn test_next_lane_count() {
+        assert_eq!(next_lane_count(nz(5), nz(32)), Some(nz(10)));
+        assert_eq!(next_lane_count(nz(10), nz(32)), Some(nz(20)));
+        assert_eq!(next_lane_count(nz(20), nz(32)), Some(nz(32)));
+        assert_eq!(next_lane_count(nz(32), nz(32)), None);
+        assert_eq!(next_lane_count(nz(5), nz(5)), None);
+        assert_eq!(next_lane_count(nz(40000), nz(u16::MAX)), Some(nz(u16::MAX)));
+    }
+
+    #[test]
+    fn test_retry_lane_count() {
+        let escalating = [PunchFailure::Failed.into(), PunchFailure::Timeout.into()];
+        for error in escalating {
+            assert_eq!(retry_lane_count(&error, nz(4), Some(nz(32))), Some(nz(8)));
+            assert_eq!(retry_lane_count(&error, nz(32), Some(nz(32))), None);
+            assert_eq!(retry_lane_count(&error, nz(4), None), None);
+        }
+
+        let non_escalating = [
+            Error::new(ErrorKind::InvalidData, "Invalid error code: InvalidLength"),
+            Error::new(ErrorKind::InvalidInput, "Lane count too large"),
+            Error::new(ErrorKind::UnexpectedEof, "No connection code was entered"),
+            Error::new(ErrorKind::Other, "Client-server mismatch"),
+            Error::new(ErrorKind::TimedOut, "Failed to establish a connection before the timeout"),
+        ];
+
+        for error in non_escalating {
+            assert_eq!(retry_lane_count(&error, nz(4), Some(nz(32))), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_binds_more_sockets() {
+        let sockets = bind_punch_sockets(None, nz(2)).unwrap();
+
+        let mut bound_counts = Vec::new();
+        let result = retry_punch(sockets, None, nz(2), Some(nz(8)), |sockets| {
+            bound_counts.push(sockets.len());
+            let succeed = sockets.len() == 8;
+            async move {
+                match succeed {
+                    true => Ok(sockets.len()),
+                    false => Err(PunchFailure::Failed.into()),
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 8);
+        assert_eq!(bound_counts, [2, 4, 8]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_at_max_lane_count() {
+        let sockets = bind_punch_sockets(None, nz(2)).unwrap();
+
+        let mut bound_counts = Vec::new();
+        let result = retry_punch(sockets, None, nz(2), Some(nz(4)), |sockets| {
+            bound_counts.push(sockets.len());
+            async { Err::<(), _>(PunchFailure::Timeout.into()) }
+        })
+        .await;
+
+        let error = result.unwrap_err();
+        assert_eq!(PunchFailure::from_error(&error), Some(PunchFailure::Timeout));
+        assert_eq!(bound_counts, [2, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_public_ip() {
+        let my_ip = Ipv4Addr::new(1, 2, 3, 4);
+        for no_public_ip in [false, true] {
+            let result = resolve_public_ip(Some(IpAddr::V4(my_ip)), no_public_ip, || async {
+                panic!("Public IP discovery shouldn't run when an IP was given")
+            })
+            .await;
+            assert_eq!(result.unwrap(), my_ip);
+        }
+
+        let discovered_ip = Ipv4Addr::new(5, 6, 7, 8);
+        let result = resolve_public_ip(None, false, || async move { Ok(discovered_ip) }).await;
+        assert_eq!(result.unwrap(), discovered_ip);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_public_ip_disabled() {
+        let result = resolve_public_ip(None, true, || async {
+            panic!("Public IP discovery shouldn't run when it's disabled")
+        })
+        .await;
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidInput);
+    }
+}
@@ -1,12 +1,28 @@
+//! Creation and configuration of the QUIC endpoints used for connecting to peers.
+//!
+//! Servers remember the TLS sessions they establish, so a client that reconnects with a session
+//! ticket can resume its session and skip part of the handshake. This is server-side support only:
+//! clients don't keep tickets across runs and connect only once per run, so the portal client
+//! itself never resumes a session.
+//!
+//! QUIC 0-RTT is disabled, since data sent as 0-RTT can be replayed by an attacker and none of the
+//! requests in the tunneling protocol are idempotent (opening a connection or starting a remote
+//! tunnel has side effects on the server).
+
 use std::{io, net::SocketAddr, sync::Arc, time::Duration};
 
 use quinn::{ClientConfig, Endpoint, EndpointConfig, IdleTimeout, ServerConfig, TokioRuntime, TransportConfig, VarInt};
 
+use rustls::server::{ServerSessionMemoryCache, StoresServerSessions};
+
 use crate::shared_socket::SharedUdpSocket;
 
 pub const KEEPALIVE_INTERVAL_PERIOD_MILLIS: u64 = 1000;
 pub const MAX_IDLE_TIMEOUT_MILLIS: u32 = 4000;
 
+/// The maximum amount of TLS sessions the server remembers for clients to resume.
+pub const MAX_SERVER_STORED_SESSIONS: usize = 256;
+
 pub enum EndpointSocketSource {
     Simple(std::net::UdpSocket),
     Shared(SharedUdpSocket),
@@ -65,12 +81,29 @@ pub fn configure_client() -> ClientConfig {
 }
 
 pub fn configure_server() -> (ServerConfig, Vec<u8>) {
+    configure_server_with_session_storage(ServerSessionMemoryCache::new(MAX_SERVER_STORED_SESSIONS))
+}
+
+pub fn configure_server_with_session_storage(
+    session_storage: Arc<dyn StoresServerSessions + Send + Sync>,
+) -> (ServerConfig, Vec<u8>) {
     let cert = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
     let cert_der = cert.serialize_der().unwrap();
     let priv_key = rustls::PrivateKey(cert.serialize_private_key_der());
     let cert_chain = vec![rustls::Certificate(cert_der.clone())];
 
-    let mut server_config = ServerConfig::with_single_cert(cert_chain, priv_key).unwrap();
+    let mut crypto = rustls::ServerConfig::builder()
+        .with_safe_default_cipher_suites()
+        .with_safe_default_kx_groups()
+        .with_protocol_versions(&[&rustls::version::TLS13])
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(cert_chain, priv_key)
+        .unwrap();
+
+    crypto.session_storage = session_storage;
+
+    let mut server_config = ServerConfig::with_crypto(Arc::new(crypto));
     let transport_config = Arc::get_mut(&mut server_config.transport).unwrap();
     transport_config.max_concurrent_uni_streams(0_u8.into());
     transport_config.keep_alive_interval(Some(Duration::from_millis(KEEPALIVE_INTERVAL_PERIOD_MILLIS)));
@@ -100,3 +133,169 @@ impl rustls::client::ServerCertVerifier for SkipServerVerification {
         Ok(rustls::client::ServerCertVerified::assertion())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use quinn::{ClientConfig, Endpoint};
+    use rustls::{
+        client::{ClientSessionMemoryCache, ClientSessionStore, ServerName, Tls12ClientSessionValue, Tls13ClientSessionValue},
+        server::{ServerSessionMemoryCache, StoresServerSessions},
+        NamedGroup,
+    };
+
+    use super::{
+        configure_server_with_session_storage, make_endpoint, EndpointSocketSource, SkipServerVerification, MAX_SERVER_STORED_SESSIONS,
+    };
+
+    /// A [`ClientSessionStore`] that counts how many TLS 1.3 tickets were stored and taken.
+    struct CountingSessionStore {
+        inner: ClientSessionMemoryCache,
+        inserted: AtomicUsize,
+        taken: AtomicUsize,
+    }
+
+    impl ClientSessionStore for CountingSessionStore {
+        fn set_kx_hint(&self, server_name: &ServerName, group: NamedGroup) {
+            self.inner.set_kx_hint(server_name, group)
+        }
+
+        fn kx_hint(&self, server_name: &ServerName) -> Option<NamedGroup> {
+            self.inner.kx_hint(server_name)
+        }
+
+        fn set_tls12_session(&self, server_name: &ServerName, value: Tls12ClientSessionValue) {
+            self.inner.set_tls12_session(server_name, value)
+        }
+
+        fn tls12_session(&self, server_name: &ServerName) -> Option<Tls12ClientSessionValue> {
+            self.inner.tls12_session(server_name)
+        }
+
+        fn remove_tls12_session(&self, server_name: &ServerName) {
+            self.inner.remove_tls12_session(server_name)
+        }
+
+        fn insert_tls13_ticket(&self, server_name: &ServerName, value: Tls13ClientSessionValue) {
+            self.inserted.fetch_add(1, Ordering::Relaxed);
+            self.inner.insert_tls13_ticket(server_name, value)
+        }
+
+        fn take_tls13_ticket(&self, server_name: &ServerName) -> Option<Tls13ClientSessionValue> {
+            let ticket = self.inner.take_tls13_ticket(server_name);
+            if ticket.is_some() {
+                self.taken.fetch_add(1, Ordering::Relaxed);
+            }
+            ticket
+        }
+    }
+
+    /// A [`StoresServerSessions`] that counts how many sessions were found when a client tried to
+    /// resume them, which is when the server accepts a resumption.
+    struct CountingServerStorage {
+        inner: Arc<ServerSessionMemoryCache>,
+        resumed: AtomicUsize,
+    }
+
+    impl StoresServerSessions for CountingServerStorage {
+        fn put(&self, key: Vec<u8>, value: Vec<u8>) -> bool {
+            self.inner.put(key, value)
+        }
+
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.inner.get(key)
+        }
+
+        fn take(&self, key: &[u8]) -> Option<Vec<u8>> {
+            let value = self.inner.take(key);
+            if value.is_some() {
+                self.resumed.fetch_add(1, Ordering::Relaxed);
+            }
+            value
+        }
+
+        fn can_cache(&self) -> bool {
+            self.inner.can_cache()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_session_resumption() {
+        let localhost = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+
+        let server_storage = Arc::new(CountingServerStorage {
+            inner: ServerSessionMemoryCache::new(MAX_SERVER_STORED_SESSIONS),
+            resumed: AtomicUsize::new(0),
+        });
+        let server_config = configure_server_with_session_storage(Arc::clone(&server_storage) as _).0;
+        let server = Endpoint::server(server_config, localhost).unwrap();
+        let server_address = server.local_addr().unwrap();
+        let server2 = server.clone();
+        tokio::spawn(async move {
+            while let Some(connecting) = server2.accept().await {
+                if let Ok(connection) = connecting.await {
+                    tokio::spawn(async move { connection.closed().await });
+                }
+            }
+        });
+
+        let store = Arc::new(CountingSessionStore {
+            inner: ClientSessionMemoryCache::new(32),
+            inserted: AtomicUsize::new(0),
+            taken: AtomicUsize::new(0),
+        });
+
+        // The portal client never reconnects, so this client keeps its tickets in its own store to
+        // try resuming the session.
+        let mut crypto = rustls::ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(SkipServerVerification::new())
+            .with_no_client_auth();
+        crypto.resumption = rustls::client::Resumption::store(Arc::clone(&store) as Arc<dyn ClientSessionStore>);
+
+        let client_socket = std::net::UdpSocket::bind(localhost).unwrap();
+        let mut client = make_endpoint(EndpointSocketSource::Simple(client_socket), false, false).unwrap();
+        client.set_default_client_config(ClientConfig::new(Arc::new(crypto)));
+
+        let server_name = server_address.ip().to_string();
+        let first = client.connect(server_address, &server_name).unwrap().await.unwrap();
+        assert_eq!(store.taken.load(Ordering::Relaxed), 0);
+        assert_eq!(server_storage.resumed.load(Ordering::Relaxed), 0);
+
+        // Session tickets are sent by the server after the handshake completes.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while store.inserted.load(Ordering::Relaxed) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("The server never sent a session ticket");
+
+        first.close(0u8.into(), b"");
+
+        let connecting = client.connect(server_address, &server_name).unwrap();
+        assert_eq!(store.taken.load(Ordering::Relaxed), 1);
+
+        let connecting = match connecting.into_0rtt() {
+            Ok(_) => panic!("The resumed connection shouldn't be eligible for 0-RTT"),
+            Err(connecting) => connecting,
+        };
+
+        let second = connecting.await.unwrap();
+        assert_eq!(
+            server_storage.resumed.load(Ordering::Relaxed),
+            1,
+            "The server should have accepted the resumption"
+        );
+        second.close(0u8.into(), b"");
+        server.close(0u8.into(), b"");
+    }
+}
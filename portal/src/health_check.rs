@@ -0,0 +1,254 @@
+//! Checks that everything needed for running with a given configuration works, without actually
+//! connecting to anyone. This is meant for validating a deployment before relying on it.
+
+use std::{
+    fmt,
+    io::{self, Error, ErrorKind},
+    net::{SocketAddr, UdpSocket},
+};
+
+use inlined::CompactVec;
+use portal_tunneler_proto::shared::TunnelSide;
+
+use crate::{
+    args::{ConnectMethod, StartupArguments, StartupMode},
+    connect::bind_punch_sockets_quietly,
+    endpoint::{make_endpoint, EndpointSocketSource},
+    puncher::get_public_ip::get_public_ipv4,
+    utils::{bind_listeners, UNSPECIFIED_SOCKADDR_V4, UNSPECIFIED_SOCKADDR_V6},
+};
+
+/// The outcome of a single check, with either a short description of what was found or the error
+/// that made it fail.
+pub struct HealthCheck {
+    pub name: String,
+    pub result: io::Result<String>,
+}
+
+/// The outcomes of all the checks ran by [`run_health_check`].
+pub struct HealthReport {
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    fn push<S: Into<String>>(&mut self, name: S, result: io::Result<String>) {
+        self.checks.push(HealthCheck { name: name.into(), result });
+    }
+
+    /// Gets whether all the checks passed.
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.result.is_ok())
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.result {
+                Ok(details) => writeln!(f, "[PASS] {}: {details}", check.name)?,
+                Err(error) => writeln!(f, "[FAIL] {}: {error}", check.name)?,
+            }
+        }
+
+        let failed = self.checks.iter().filter(|check| check.result.is_err()).count();
+        match failed {
+            0 => write!(f, "All {} checks passed", self.checks.len()),
+            _ => write!(f, "{failed} of {} checks failed", self.checks.len()),
+        }
+    }
+}
+
+/// Runs every check that applies to the given arguments: binding the UDP sockets, reaching the
+/// public IP discovery endpoint (only when hole-punching without `--my-ip`), binding the listeners
+/// of local tunnels, and creating the QUIC endpoints.
+pub async fn run_health_check(startup_args: &StartupArguments) -> HealthReport {
+    let mut report = HealthReport { checks: Vec::new() };
+    let is_server = startup_args.startup_mode.is_server();
+
+    let mut sockets = Vec::new();
+    match &startup_args.connect_method {
+        ConnectMethod::Direct(addresses) => {
+            let bind_addresses = direct_bind_addresses(addresses, is_server);
+            if bind_addresses.is_empty() {
+                let error = Error::new(ErrorKind::InvalidInput, "No addresses to bind a socket at");
+                report.push("Bind UDP socket", Err(error));
+            }
+
+            for address in bind_addresses {
+                let result = UdpSocket::bind(address);
+                report_bound_socket(&mut report, format!("Bind UDP socket at {address}"), result, &mut sockets);
+            }
+        }
+        ConnectMethod::Punch(punch_config) => {
+            let result = bind_punch_sockets_quietly(punch_config.port_start, punch_config.lane_count)
+                .and_then(|mut sockets| sockets.swap_remove(0).into_std());
+            report_bound_socket(&mut report, String::from("Bind UDP socket"), result, &mut sockets);
+        }
+    }
+
+    if let ConnectMethod::Punch(punch_config) = &startup_args.connect_method {
+        if punch_config.my_ip.is_none() && !punch_config.no_public_ip {
+            let result = get_public_ipv4().await.map(|ip| format!("Public IP is {ip}"));
+            report.push("Reach public IP discovery endpoint", result);
+        }
+    }
+
+    if let StartupMode::Client(client_config) = &startup_args.startup_mode {
+        for spec in client_config.tunnels.iter().filter(|spec| spec.side == TunnelSide::Local) {
+            let result = bind_listeners(spec.listen_address.as_ref()).await.map(|listeners| {
+                let addresses = listeners
+                    .iter()
+                    .filter_map(|listener| listener.local_addr().ok())
+                    .collect::<CompactVec<3, _>>();
+                format!("Listening at {}", addresses.fmt_joined(", "))
+            });
+
+            report.push(format!("Bind listener for tunnel {}", spec.index), result);
+        }
+    }
+
+    for (address, socket) in sockets {
+        let result = make_endpoint(EndpointSocketSource::Simple(socket), !is_server, is_server);
+        report.push(format!("Create QUIC endpoint at {address}"), result.map(|_| String::from("Created")));
+    }
+
+    report
+}
+
+/// Reports the result of binding a UDP socket, keeping the socket along with its address if it
+/// was bound so a QUIC endpoint can later be created with it.
+fn report_bound_socket(
+    report: &mut HealthReport,
+    name: String,
+    result: io::Result<UdpSocket>,
+    sockets: &mut Vec<(SocketAddr, UdpSocket)>,
+) {
+    match result.and_then(|socket| socket.local_addr().map(|address| (address, socket))) {
+        Ok((address, socket)) => {
+            report.push(name, Ok(format!("Bound at {address}")));
+            sockets.push((address, socket));
+        }
+        Err(error) => report.push(name, Err(error)),
+    }
+}
+
+/// Gets the addresses to bind UDP sockets at for a direct connection, like the ones bound when
+/// actually connecting. Servers bind every address they listen at, while clients bind an
+/// unspecified address for each address family among the ones to connect to.
+fn direct_bind_addresses(addresses: &CompactVec<2, SocketAddr>, is_server: bool) -> CompactVec<2, SocketAddr> {
+    let mut bind_addresses = CompactVec::<2, SocketAddr>::new();
+    match is_server {
+        true => bind_addresses.extend(addresses.iter().copied()),
+        false => {
+            if addresses.iter().any(|a| a.is_ipv4()) {
+                bind_addresses.push(UNSPECIFIED_SOCKADDR_V4);
+            }
+
+            if addresses.iter().any(|a| a.is_ipv6()) {
+                bind_addresses.push(UNSPECIFIED_SOCKADDR_V6);
+            }
+        }
+    }
+
+    bind_addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+
+    use inlined::CompactVec;
+    use portal_tunneler_proto::shared::{AddressOrDomainname, TunnelSide, TunnelSpec, TunnelTarget};
+
+    use crate::args::{ConnectMethod, StartClientConfig, StartServerConfig, StartupArguments, StartupMode};
+
+    use super::run_health_check;
+
+    fn localhost(port: u16) -> SocketAddr {
+        SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port)
+    }
+
+    fn startup_args(connect_method: ConnectMethod, startup_mode: StartupMode) -> StartupArguments {
+        StartupArguments::new(false, false, false, None, connect_method, startup_mode)
+    }
+
+    fn client_with_tunnel(listen_address: SocketAddr) -> StartupArguments {
+        let mut client_config = StartClientConfig::new();
+        client_config.tunnels.push(TunnelSpec {
+            index: 0,
+            side: TunnelSide::Local,
+            target: TunnelTarget::Socks,
+            listen_address: AddressOrDomainname::Address(listen_address),
+        });
+
+        startup_args(
+            ConnectMethod::Direct(CompactVec::from(localhost(5995))),
+            StartupMode::Client(client_config),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_all_pass() {
+        let server_args = startup_args(
+            ConnectMethod::Direct(CompactVec::from(localhost(0))),
+            StartupMode::Server(StartServerConfig::new()),
+        );
+
+        let report = run_health_check(&server_args).await;
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.checks.len(), 2);
+
+        let report = run_health_check(&client_with_tunnel(localhost(0))).await;
+        assert!(report.passed(), "{report}");
+        assert_eq!(report.checks.len(), 3);
+        assert!(report.to_string().ends_with("All 3 checks passed"));
+    }
+
+    #[tokio::test]
+    async fn test_unbindable_port() {
+        let taken_udp = UdpSocket::bind(localhost(0)).unwrap();
+        let server_args = startup_args(
+            ConnectMethod::Direct(CompactVec::from(taken_udp.local_addr().unwrap())),
+            StartupMode::Server(StartServerConfig::new()),
+        );
+
+        let report = run_health_check(&server_args).await;
+        assert!(!report.passed());
+        assert!(report.checks[0].result.is_err());
+        assert!(report.to_string().contains("[FAIL] Bind UDP socket"));
+
+        let taken_tcp = TcpListener::bind(localhost(0)).unwrap();
+        let report = run_health_check(&client_with_tunnel(taken_tcp.local_addr().unwrap())).await;
+        assert!(!report.passed());
+        assert!(report
+            .checks
+            .iter()
+            .any(|check| check.name == "Bind listener for tunnel 0" && check.result.is_err()));
+        assert!(report.to_string().ends_with("1 of 3 checks failed"));
+    }
+
+    #[tokio::test]
+    async fn test_checks_every_listen_address() {
+        let taken_udp = UdpSocket::bind(localhost(0)).unwrap();
+        let taken_address = taken_udp.local_addr().unwrap();
+
+        let mut addresses = CompactVec::new();
+        addresses.push(localhost(0));
+        addresses.push(taken_address);
+        let server_args = startup_args(ConnectMethod::Direct(addresses), StartupMode::Server(StartServerConfig::new()));
+
+        let report = run_health_check(&server_args).await;
+        assert!(!report.passed(), "{report}");
+
+        let names = report.checks.iter().map(|check| check.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names.len(), 3, "{report}");
+        assert_eq!(names[0], format!("Bind UDP socket at {}", localhost(0)));
+        assert_eq!(names[1], format!("Bind UDP socket at {taken_address}"));
+        assert!(names[2].starts_with("Create QUIC endpoint at "));
+
+        assert!(report.checks[0].result.is_ok());
+        assert!(report.checks[1].result.is_err());
+        assert!(report.checks[2].result.is_ok());
+        assert!(report.to_string().ends_with("1 of 3 checks failed"));
+    }
+}
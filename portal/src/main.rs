@@ -6,6 +6,7 @@ use std::{
     io::Error,
     pin::Pin,
     process::exit,
+    rc::Rc,
     task::Poll,
 };
 
@@ -18,12 +19,14 @@ use crate::{
     args::{ConnectMethod, StartupMode},
     endpoint::EndpointSocketSource,
     puncher::PunchConnectResult,
+    server::run::AcceptTimeout,
 };
 
 mod args;
 mod client;
 mod connect;
 mod endpoint;
+mod health_check;
 mod puncher;
 mod server;
 mod shared_socket;
@@ -39,7 +42,7 @@ fn main() {
         Ok(arguments) => arguments,
     };
 
-    let startup_args = match arguments {
+    let (startup_args, is_health_check) = match arguments {
         ArgumentsRequest::Version => {
             println!("{}", args::get_version_string());
             println!("// TODO: Write a funny message");
@@ -49,19 +52,30 @@ fn main() {
             println!("{}", args::get_help_string());
             return;
         }
-        ArgumentsRequest::Run(startup_args) => startup_args,
+        ArgumentsRequest::Run(startup_args) => (startup_args, false),
+        ArgumentsRequest::HealthCheck(startup_args) => (startup_args, true),
     };
 
-    let runtime_result = tokio::runtime::Builder::new_current_thread().enable_all().build();
-
-    let result = match runtime_result {
-        Ok(runtime) => LocalSet::new().block_on(&runtime, async_main(startup_args)),
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
         Err(err) => {
             eprintln!("Failed to start Tokio runtime: {err}");
             exit(1);
         }
     };
 
+    if is_health_check {
+        let report = LocalSet::new().block_on(&runtime, health_check::run_health_check(&startup_args));
+        println!("{report}");
+        if !report.passed() {
+            exit(1);
+        }
+
+        return;
+    }
+
+    let result = LocalSet::new().block_on(&runtime, async_main(startup_args));
+
     if let Err(error) = result {
         println!("Program finished with error: {error}\n\nDebug print: {error:?}");
     }
@@ -70,10 +84,15 @@ fn main() {
 async fn async_main(startup_args: StartupArguments) -> Result<(), Error> {
     println!("Startup arguments: {startup_args:?}");
 
+    let accept_timeout = match &startup_args.startup_mode {
+        StartupMode::Server(server_config) => server_config.accept_timeout,
+        StartupMode::Client(_) => None,
+    };
+
     let (maybe_socket, mut addresses, mut background_task_handle) = match startup_args.connect_method {
         ConnectMethod::Direct(addresses) => (None, addresses, None),
         ConnectMethod::Punch(punch_config) => {
-            let punch_result = connect::punch(punch_config, startup_args.startup_mode.is_server()).await?;
+            let punch_result = connect::punch(punch_config, startup_args.startup_mode.is_server(), accept_timeout).await?;
 
             let (socket, address, background_task_handle) = match punch_result {
                 PunchConnectResult::Connect(socket, to_address) => {
@@ -97,10 +116,10 @@ async fn async_main(startup_args: StartupArguments) -> Result<(), Error> {
     match startup_args.startup_mode {
         StartupMode::Client(client_config) => {
             let (endpoint, connection) = connect::connect_client(maybe_socket, addresses).await?;
-            background_task_handle.inspect(|handle| handle.abort());
+            background_task_handle.inspect(|handle| handle.stop());
 
-            match crate::client::run::run_client(connection, client_config).await {
-                Ok(()) => {}
+            match crate::client::run::run_client(connection, client_config, startup_args.summary, startup_args.socks_timeout).await {
+                Ok(_) => {}
                 Err(error) => eprintln!("Client finished with error: {error}"),
             }
             endpoint.wait_idle().await;
@@ -116,10 +135,16 @@ async fn async_main(startup_args: StartupArguments) -> Result<(), Error> {
             let mut handles = Vec::new();
             handles.reserve_exact(endpoints.len());
 
+            // All the endpoints share the accept timeout, so a connection on any of them stops it.
+            let accept_timeout = accept_timeout.map(|timeout| Rc::new(AcceptTimeout::new(timeout)));
+
             for endpoint in endpoints {
                 let maybe_handle = background_task_handle.take();
+                let accept_timeout = accept_timeout.clone();
+                let summary = startup_args.summary;
+                let socks_timeout = startup_args.socks_timeout;
                 let handle = tokio::task::spawn_local(async move {
-                    crate::server::run::run_server(endpoint, maybe_handle, address_filter).await;
+                    crate::server::run::run_server(endpoint, maybe_handle, address_filter, summary, accept_timeout, socks_timeout).await;
                 });
 
                 handles.push(handle);
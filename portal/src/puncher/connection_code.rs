@@ -5,12 +5,19 @@ use std::{
 };
 
 use base64::Engine;
+use inlined::InlineString;
 
 use crate::utils::get_current_timestamp;
 
+use super::wordlist::{find_word, WORDS};
+
 pub const CONNECTION_CODE_MAX_LENGTH_BYTES: usize = 17 + 2 + 2 + 8 + 2;
 pub const CONNECTION_STRING_MAX_LENGTH_CHARS: usize = (CONNECTION_CODE_MAX_LENGTH_BYTES * 4 + 2) / 3;
 
+/// The maximum length of a user-entered connection code, including any whitespace around or inside
+/// it, that can still be parsed as the string form.
+pub const ENTERED_CODE_MAX_LENGTH: usize = 256;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ConnectionCode {
     pub address: IpAddr,
@@ -28,6 +35,14 @@ pub enum DeserializeError {
     OverflowingLaneCount,
     BadChecksum,
     TooLong,
+    UnknownWord,
+    BadChecksumWord,
+}
+
+/// Calculates the checksum word appended when encoding a connection code as words. Since this is
+/// a sum of all the bytes, a single wrong word always changes it.
+fn calc_checksum_word(buf: &[u8]) -> u8 {
+    buf.iter().fold(0x69u8, |acc, ele| acc.wrapping_add(*ele))
 }
 
 fn calc_checksum(buf: &[u8]) -> u16 {
@@ -102,6 +117,29 @@ impl ConnectionCode {
         s
     }
 
+    /// Serializes this connection code as a sequence of words separated by spaces, one word for
+    /// each byte followed by a checksum word. This is longer than [`serialize_to_string`], but
+    /// much easier to dictate.
+    ///
+    /// The checksum word only lets [`deserialize_from_words`] detect a single wrong word. It can't
+    /// tell which word is wrong, so it can't correct it either.
+    ///
+    /// [`serialize_to_string`]: ConnectionCode::serialize_to_string
+    /// [`deserialize_from_words`]: ConnectionCode::deserialize_from_words
+    pub fn serialize_to_words(&self) -> String {
+        let mut buf = [0u8; CONNECTION_CODE_MAX_LENGTH_BYTES];
+        let len = self.serialize_to_bytes(&mut buf);
+
+        let mut s = String::new();
+        for ele in &buf[..len] {
+            s.push_str(WORDS[*ele as usize]);
+            s.push(' ');
+        }
+
+        s.push_str(WORDS[calc_checksum_word(&buf[..len]) as usize]);
+        s
+    }
+
     pub fn deserialize_from_bytes(buf: &[u8]) -> Result<ConnectionCode, DeserializeError> {
         fn check_buf_len(buf: &[u8], min_len: usize) -> Result<(), DeserializeError> {
             match buf.len() >= min_len {
@@ -110,7 +148,7 @@ impl ConnectionCode {
             }
         }
 
-        check_buf_len(buf, 0)?;
+        check_buf_len(buf, 1)?;
         let mut index;
 
         let address = match buf[0] {
@@ -180,6 +218,72 @@ impl ConnectionCode {
 
         Self::deserialize_from_bytes(&buf[..buf_len])
     }
+
+    /// Deserializes a connection code from the words produced by [`serialize_to_words`]. Words may
+    /// be separated by whitespace or dashes, are case-insensitive, and are recognized by their first
+    /// four letters if they don't match exactly, which tolerates typos past those letters.
+    ///
+    /// No errors are corrected. A word that can't be recognized fails with
+    /// [`DeserializeError::UnknownWord`], and a recognized but wrong word is detected by the
+    /// checksum word, failing with [`DeserializeError::BadChecksumWord`].
+    ///
+    /// [`serialize_to_words`]: ConnectionCode::serialize_to_words
+    pub fn deserialize_from_words(string: &str) -> Result<ConnectionCode, DeserializeError> {
+        let mut buf = [0u8; CONNECTION_CODE_MAX_LENGTH_BYTES + 1];
+        let mut len = 0;
+
+        for word in string.split(|c: char| c.is_whitespace() || c == '-').filter(|w| !w.is_empty()) {
+            if len == buf.len() {
+                return Err(DeserializeError::TooLong);
+            }
+
+            buf[len] = find_word(word).ok_or(DeserializeError::UnknownWord)?;
+            len += 1;
+        }
+
+        let (checksum_word, buf) = match buf[..len].split_last() {
+            Some(t) => t,
+            None => return Err(DeserializeError::UnexpectedEnd),
+        };
+
+        if *checksum_word != calc_checksum_word(buf) {
+            return Err(DeserializeError::BadChecksumWord);
+        }
+
+        Self::deserialize_from_bytes(buf)
+    }
+
+    /// Parses a connection code entered by a user, which may be either in the string form produced
+    /// by [`serialize_to_string`] or in the words form produced by [`serialize_to_words`].
+    ///
+    /// Since the string form may itself contain dashes, it is tried first, after removing all the
+    /// whitespace with [`InlineString::normalize_code`] (e.g. from a code that got wrapped when
+    /// pasted). The words form is parsed from the input as-is, since it needs the whitespace
+    /// between words. If neither form matches, the error from parsing as words is
+    /// returned if the input contains inner whitespace or dashes, and the one from parsing as a
+    /// string otherwise.
+    ///
+    /// [`serialize_to_string`]: ConnectionCode::serialize_to_string
+    /// [`serialize_to_words`]: ConnectionCode::serialize_to_words
+    pub fn parse(string: &str) -> Result<ConnectionCode, DeserializeError> {
+        let mut normalized = InlineString::<ENTERED_CODE_MAX_LENGTH>::new();
+        let string_error = match normalized.push_str_all(string) {
+            Ok(()) => {
+                normalized.normalize_code();
+                match Self::deserialize_from_str(&normalized) {
+                    Ok(code) => return Ok(code),
+                    Err(error) => error,
+                }
+            }
+            Err(_) => DeserializeError::TooLong,
+        };
+
+        match Self::deserialize_from_words(string) {
+            Ok(code) => Ok(code),
+            Err(error) if string.trim().contains(|c: char| c.is_whitespace() || c == '-') => Err(error),
+            Err(_) => Err(string_error),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -231,4 +335,102 @@ mod tests {
         let result = ConnectionCode::deserialize_from_str(&s);
         assert_eq!(result, Err(DeserializeError::BadChecksum))
     }
+
+    #[test]
+    fn test_words() {
+        let addresses: [IpAddr; 3] = [
+            IpAddr::V4("69.22.4.0".parse().unwrap()),
+            IpAddr::V4("123.210.123.210".parse().unwrap()),
+            IpAddr::V6("1234::9c9:3ab2:f332:23ec".parse().unwrap()),
+        ];
+
+        for address in addresses {
+            for (port_start, lane_count) in [(0, 1), (1920, 5), (48912, 10000)] {
+                let code = ConnectionCode::new(address, port_start, NonZeroU16::new(lane_count).unwrap());
+                let words = code.serialize_to_words();
+                assert_eq!(ConnectionCode::deserialize_from_words(&words), Ok(code));
+
+                let shouted = words.to_uppercase().replace(' ', "-");
+                assert_eq!(ConnectionCode::deserialize_from_words(&shouted), Ok(code));
+            }
+        }
+    }
+
+    #[test]
+    fn test_words_misspelled_ending() {
+        let code = ConnectionCode::new("1.2.3.4".parse().unwrap(), 5000, NonZeroU16::new(5).unwrap());
+        let words = code
+            .serialize_to_words()
+            .split(' ')
+            .map(|w| format!("{w}s"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(ConnectionCode::deserialize_from_words(&words), Ok(code));
+    }
+
+    #[test]
+    fn test_words_wrong_word() {
+        let code = ConnectionCode::new("69.22.4.0".parse().unwrap(), 43434, NonZeroU16::new(69).unwrap());
+        let words = code.serialize_to_words();
+
+        for i in 0..words.split(' ').count() - 1 {
+            let mut split = words.split(' ').collect::<Vec<_>>();
+            split[i] = match split[i] {
+                "apple" => "zebra",
+                _ => "apple",
+            };
+
+            let result = ConnectionCode::deserialize_from_words(&split.join(" "));
+            assert_eq!(result, Err(DeserializeError::BadChecksumWord));
+        }
+    }
+
+    #[test]
+    fn test_words_errors() {
+        assert_eq!(ConnectionCode::deserialize_from_words(""), Err(DeserializeError::UnexpectedEnd));
+        assert_eq!(
+            ConnectionCode::deserialize_from_words("apple xylophone"),
+            Err(DeserializeError::UnknownWord)
+        );
+        assert_eq!(
+            ConnectionCode::deserialize_from_words("apple cat"),
+            Err(DeserializeError::UnknownWord)
+        );
+
+        let code = ConnectionCode::new("1.2.3.4".parse().unwrap(), 5000, NonZeroU16::new(5).unwrap());
+        let words = format!("{} {}", code.serialize_to_words(), "apple ".repeat(20));
+        assert_eq!(ConnectionCode::deserialize_from_words(&words), Err(DeserializeError::TooLong));
+    }
+
+    #[test]
+    fn test_parse() {
+        let code = ConnectionCode::new("123.210.123.210".parse().unwrap(), 1920, NonZeroU16::new(5).unwrap());
+        let words = code.serialize_to_words();
+
+        assert_eq!(ConnectionCode::parse(&code.serialize_to_string()), Ok(code));
+        assert_eq!(ConnectionCode::parse(&words), Ok(code));
+        assert_eq!(ConnectionCode::parse(&words.replace(' ', "-")), Ok(code));
+        assert_eq!(ConnectionCode::parse(&words.replace(' ', "-").to_uppercase()), Ok(code));
+
+        assert_eq!(ConnectionCode::parse(&format!("  {words} \r\n")), Ok(code));
+
+        assert_eq!(ConnectionCode::parse("not-a-code"), Err(DeserializeError::UnknownWord));
+        assert_eq!(ConnectionCode::parse("AAAA!"), Err(DeserializeError::InvalidBase64));
+        assert_eq!(ConnectionCode::parse(" AAAA!\n"), Err(DeserializeError::InvalidBase64));
+        assert_eq!(ConnectionCode::parse(""), Err(DeserializeError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_parse_pasted_with_whitespace() {
+        let code = ConnectionCode::new("123.210.123.210".parse().unwrap(), 1920, NonZeroU16::new(5).unwrap());
+        let s = code.serialize_to_string();
+        let (first, second) = s.split_at(s.len() / 2);
+        let (second, third) = second.split_at(second.len() / 2);
+        let pasted = format!(" \t{first}\n{second} \r\n {third}  \r\n");
+
+        assert_eq!(ConnectionCode::parse(&pasted), Ok(code));
+
+        let padded = format!("{s}{}", " ".repeat(300));
+        assert_eq!(ConnectionCode::parse(&padded), Err(DeserializeError::TooLong));
+    }
 }
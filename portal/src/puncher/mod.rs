@@ -1,13 +1,15 @@
 use std::{
+    fmt,
     io::{Error, ErrorKind},
     net::{IpAddr, SocketAddr},
     num::NonZeroU16,
-    time::Duration,
+    rc::Rc,
+    time::{Duration, Instant},
 };
 
 use portal_puncher_sm as sm;
 
-use tokio::{net::UdpSocket, select, task::JoinHandle};
+use tokio::{net::UdpSocket, select, sync::Notify};
 
 use crate::{
     shared_socket::SharedUdpSocket,
@@ -17,10 +19,60 @@ use crate::{
 pub mod connection_code;
 pub mod get_public_ip;
 pub mod socket_binder;
+mod wordlist;
 
 pub enum PunchConnectResult {
     Connect(UdpSocket, SocketAddr),
-    Listen(SharedUdpSocket, SocketAddr, JoinHandle<()>),
+    Listen(SharedUdpSocket, SocketAddr, BackgroundTaskHandle),
+}
+
+/// A handle to the task that keeps the punched lane open while a server waits for the client to
+/// connect, which can be used to tell it to stop.
+#[derive(Clone)]
+pub struct BackgroundTaskHandle {
+    stop: Rc<Notify>,
+}
+
+impl BackgroundTaskHandle {
+    /// Signals the background task to stop. If it's in the middle of sending a packet, it finishes
+    /// sending it before exiting.
+    pub fn stop(&self) {
+        self.stop.notify_one();
+    }
+}
+
+/// The ways in which hole punching can fail where retrying, possibly with more lanes, might help.
+/// These are returned by [`punch_connection`] wrapped in an [`Error`], and can be told apart from
+/// any other error with [`PunchFailure::from_error`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PunchFailure {
+    Failed,
+    Timeout,
+}
+
+impl PunchFailure {
+    /// Gets the [`PunchFailure`] inside an error returned by [`punch_connection`], or `None` if the
+    /// error was caused by something else.
+    pub fn from_error(error: &Error) -> Option<Self> {
+        error.get_ref()?.downcast_ref::<Self>().copied()
+    }
+}
+
+impl fmt::Display for PunchFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Failed => write!(f, "Failed to establish a connection on any lane"),
+            Self::Timeout => write!(f, "Failed to establish a connection before the timeout"),
+        }
+    }
+}
+
+impl std::error::Error for PunchFailure {}
+
+impl From<PunchFailure> for Error {
+    fn from(value: PunchFailure) -> Self {
+        Error::new(ErrorKind::Other, value)
+    }
 }
 
 pub async fn punch_connection(
@@ -29,10 +81,11 @@ pub async fn punch_connection(
     remote_address: IpAddr,
     remote_port_start: NonZeroU16,
     lane_count: NonZeroU16,
+    accept_timeout: Option<Duration>,
 ) -> Result<PunchConnectResult, Error> {
     let port_start = NonZeroU16::new(sockets[0].local_addr().unwrap().port()).unwrap();
 
-    let mut puncher = sm::Puncher::new(
+    let mut puncher = sm::Puncher::try_new(
         is_server,
         port_start,
         remote_address,
@@ -40,7 +93,8 @@ pub async fn punch_connection(
         lane_count,
         Duration::from_millis(1500),
         Duration::from_secs(20),
-    );
+    )
+    .map_err(|error| Error::new(ErrorKind::InvalidInput, error.to_string()))?;
 
     let mut buf = [0u8; sm::MAX_REASONABLE_PAYLOAD];
     let mut packet_counter = 0u32;
@@ -99,12 +153,8 @@ pub async fn punch_connection(
             sm::PuncherAction::Wait => {}
             sm::PuncherAction::Connect(ports) => break ports,
             sm::PuncherAction::Listen(ports) => break ports,
-            sm::PuncherAction::Failed => {
-                return Err(Error::new(ErrorKind::Other, "Failed to establish a connection on any lane"));
-            }
-            sm::PuncherAction::Timeout => {
-                return Err(Error::new(ErrorKind::Other, "Failed to establish a connection before the timeout"));
-            }
+            sm::PuncherAction::Failed => return Err(PunchFailure::Failed.into()),
+            sm::PuncherAction::Timeout => return Err(PunchFailure::Timeout.into()),
             sm::PuncherAction::ClientServerMismatch => {
                 return Err(Error::new(
                     ErrorKind::Other,
@@ -125,8 +175,12 @@ pub async fn punch_connection(
         true => {
             let socket = SharedUdpSocket::new(socket).unwrap();
             let socket2 = SharedUdpSocket::clone(&socket);
-            let handle = tokio::task::spawn_local(async move {
-                server_background_task(socket2, puncher, packet_counter).await;
+            let handle = BackgroundTaskHandle {
+                stop: Rc::new(Notify::new()),
+            };
+            let stop = Rc::clone(&handle.stop);
+            tokio::task::spawn_local(async move {
+                server_background_task(socket2, puncher, packet_counter, accept_timeout, stop).await;
             });
 
             PunchConnectResult::Listen(socket, remote_address, handle)
@@ -137,8 +191,18 @@ pub async fn punch_connection(
     Ok(result)
 }
 
-async fn server_background_task(socket: SharedUdpSocket, mut puncher: sm::Puncher, mut packet_counter: u32) {
+/// Keeps sending packets to the client through the selected lane until `stop` is notified, which
+/// happens when the client connects. If `accept_timeout` is `Some` and it elapses before that, the
+/// task stops on its own.
+async fn server_background_task(
+    socket: SharedUdpSocket,
+    mut puncher: sm::Puncher,
+    mut packet_counter: u32,
+    accept_timeout: Option<Duration>,
+    stop: Rc<Notify>,
+) {
     println!("Started background task to keep sending packets");
+    let accept_deadline = accept_timeout.map(|timeout| Instant::now() + timeout);
     let mut buf = [0u8; sm::MAX_REASONABLE_PAYLOAD];
     loop {
         println!("Another background tick");
@@ -162,6 +226,78 @@ async fn server_background_task(socket: SharedUdpSocket, mut puncher: sm::Punche
             packet_counter += 1;
         }
 
-        sleep_until_if_some(puncher.next_tick_instant()).await;
+        select! {
+            biased;
+            _ = stop.notified() => {
+                println!("Client connected, stopping background task");
+                return;
+            }
+            _ = sleep_until_if_some(accept_deadline) => {
+                println!("No connection was accepted before the accept timeout, stopping background task");
+                return;
+            }
+            _ = sleep_until_if_some(puncher.next_tick_instant()) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        num::NonZeroU16,
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use portal_puncher_sm as sm;
+    use tokio::{net::UdpSocket, sync::Notify};
+
+    use super::server_background_task;
+    use crate::shared_socket::SharedUdpSocket;
+
+    /// Creates a socket and a server puncher whose "client" is that same socket, which never answers.
+    async fn make_lonely_server() -> (SharedUdpSocket, sm::Puncher) {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = NonZeroU16::new(socket.local_addr().unwrap().port()).unwrap();
+        let socket = SharedUdpSocket::new(socket).unwrap();
+
+        let puncher = sm::Puncher::new(
+            true,
+            port,
+            IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port,
+            NonZeroU16::MIN,
+            Duration::from_millis(50),
+            Duration::from_secs(20),
+        );
+
+        (socket, puncher)
+    }
+
+    #[tokio::test]
+    async fn test_server_background_task_accept_timeout() {
+        let (socket, puncher) = make_lonely_server().await;
+
+        let start = Instant::now();
+        let task = server_background_task(socket, puncher, 0, Some(Duration::from_millis(300)), Rc::new(Notify::new()));
+        let result = tokio::time::timeout(Duration::from_secs(5), task).await;
+
+        assert!(result.is_ok(), "The background task didn't stop after the accept timeout");
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_server_background_task_stop() {
+        let (socket, puncher) = make_lonely_server().await;
+        let stop = Rc::new(Notify::new());
+
+        let task = server_background_task(socket, puncher, 0, None, Rc::clone(&stop));
+        let (result, ()) = tokio::join!(tokio::time::timeout(Duration::from_secs(5), task), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            stop.notify_one();
+        });
+
+        assert!(result.is_ok(), "The background task didn't stop after being signaled");
     }
 }
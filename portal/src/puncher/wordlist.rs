@@ -0,0 +1,37 @@
+/// The words used for encoding connection codes as words, one for each possible byte value.
+///
+/// The words are sorted, at least four letters long, and no two words start with the same four
+/// letters, so a word can be recognized by its first four letters alone.
+pub const WORDS: [&str; 256] = [
+    "acorn", "actor", "adult", "agent", "alarm", "album", "amber", "angle", "ankle", "apple", "april", "arena", "armor", "arrow", "atlas",
+    "autumn", "bacon", "badge", "bagel", "baker", "bamboo", "banjo", "basket", "beach", "beard", "bench", "berry", "bottle", "bread",
+    "brick", "bridge", "cabin", "cactus", "camel", "candle", "canyon", "carpet", "castle", "cedar", "chair", "cherry", "chess", "circus",
+    "clock", "cloud", "coast", "cobra", "coffee", "comet", "coral", "cotton", "dance", "danger", "delta", "desert", "dolphin", "dragon",
+    "dream", "eagle", "earth", "elbow", "ember", "engine", "fabric", "falcon", "feather", "fence", "ferry", "fiber", "field", "finger",
+    "flute", "focus", "forest", "fossil", "frog", "frost", "fruit", "galaxy", "garden", "garlic", "gecko", "ghost", "giant", "ginger",
+    "glass", "globe", "glove", "grape", "gravel", "green", "guitar", "habit", "hammer", "harbor", "heart", "helmet", "hockey", "honey",
+    "horse", "hotel", "house", "humor", "hunter", "igloo", "image", "index", "indigo", "insect", "island", "ivory", "jacket", "jaguar",
+    "jelly", "jewel", "jigsaw", "jockey", "judge", "juice", "jungle", "kayak", "kernel", "kettle", "kidney", "kitchen", "kitten", "koala",
+    "ladder", "laptop", "lemon", "letter", "lizard", "lotus", "lunar", "magnet", "mango", "maple", "marble", "market", "meadow", "melon",
+    "metal", "mirror", "monkey", "muffin", "museum", "music", "napkin", "nature", "needle", "nephew", "nickel", "night", "noodle", "north",
+    "novel", "number", "nurse", "ocean", "office", "olive", "onion", "orange", "orbit", "orchid", "otter", "oven", "oxygen", "paddle",
+    "palace", "panda", "paper", "parrot", "peach", "pencil", "pepper", "piano", "pigeon", "pillow", "pilot", "planet", "pocket", "polar",
+    "potato", "puzzle", "queen", "quiet", "quilt", "quiz", "rabbit", "radio", "raven", "record", "ribbon", "river", "robot", "rocket",
+    "rugby", "saddle", "salmon", "school", "season", "shadow", "shark", "sheep", "shell", "silver", "singer", "skate", "sleep", "snake",
+    "soccer", "socks", "spider", "spoon", "stone", "sugar", "summer", "sunset", "swan", "table", "tailor", "target", "teapot", "tennis",
+    "tiger", "timber", "toast", "tomato", "tower", "train", "tulip", "tunnel", "turtle", "uncle", "unicorn", "union", "valley", "velvet",
+    "violin", "voyage", "wagon", "walnut", "walrus", "water", "whale", "wheat", "window", "winter", "wizard", "yacht", "yellow", "yogurt",
+    "zebra", "zenith", "zipper",
+];
+
+/// Finds the byte value encoded by a word, ignoring case. A word is also recognized if only its
+/// first four letters match, which tolerates misheard or misspelled endings (e.g. "apples").
+pub fn find_word(word: &str) -> Option<u8> {
+    let word = word.to_ascii_lowercase();
+    if let Ok(index) = WORDS.binary_search(&word.as_str()) {
+        return Some(index as u8);
+    }
+
+    let prefix = word.get(..4)?;
+    WORDS.iter().position(|w| w.starts_with(prefix)).map(|index| index as u8)
+}
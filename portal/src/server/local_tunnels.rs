@@ -7,9 +7,13 @@ use portal_tunneler_proto::{
 use quinn::{RecvStream, SendStream};
 use tokio::try_join;
 
-use crate::utils::{bind_connect, UNSPECIFIED_SOCKADDR_V4};
+use crate::utils::{bind_connect, copy_counted, SessionStats, UNSPECIFIED_SOCKADDR_V4};
 
-pub async fn handle_open_local_tunnel_stream(mut send_stream: SendStream, mut recv_stream: RecvStream) -> io::Result<()> {
+pub async fn handle_open_local_tunnel_stream(
+    stats: &SessionStats,
+    mut send_stream: SendStream,
+    mut recv_stream: RecvStream,
+) -> io::Result<()> {
     println!("Incoming connection from on tunnel");
 
     let request = OpenLocalConnectionRequest::read(&mut recv_stream).await?;
@@ -36,9 +40,11 @@ pub async fn handle_open_local_tunnel_stream(mut send_stream: SendStream, mut re
 
     let mut tcp_stream = tcp_stream_result?;
     let (mut read_half, mut write_half) = tcp_stream.split();
+    let tunnel_index = None;
+    stats.record_connection(tunnel_index);
     let result = try_join!(
-        tokio::io::copy(&mut read_half, &mut send_stream),
-        tokio::io::copy(&mut recv_stream, &mut write_half),
+        copy_counted(&mut read_half, &mut send_stream, |bytes| stats.record_sent(tunnel_index, bytes)),
+        copy_counted(&mut recv_stream, &mut write_half, |bytes| stats.record_received(tunnel_index, bytes)),
     );
 
     match result {
@@ -1,6 +1,7 @@
 use std::{
     io::{self, ErrorKind},
     rc::Rc,
+    time::Duration,
 };
 
 use portal_tunneler_proto::{
@@ -16,12 +17,17 @@ use tokio::{
     try_join,
 };
 
-use crate::{socks, utils::bind_listeners};
+use crate::{
+    socks,
+    utils::{bind_listeners, copy_counted, SessionStats},
+};
 
 pub async fn handle_start_remote_tunnels_stream(
     connection: Rc<Connection>,
+    stats: Rc<SessionStats>,
     mut send_stream: SendStream,
     mut recv_stream: RecvStream,
+    socks_timeout: Option<Duration>,
 ) -> io::Result<()> {
     loop {
         let request = match StartRemoteTunnelRequest::read(&mut recv_stream).await {
@@ -39,8 +45,9 @@ pub async fn handle_start_remote_tunnels_stream(
             let target_type = request.target_type;
             for listener in listeners {
                 let connection = Rc::clone(&connection);
+                let stats = Rc::clone(&stats);
                 tokio::task::spawn_local(async move {
-                    handle_remote_tunnel_listening(connection, listener, tunnel_id, target_type).await;
+                    handle_remote_tunnel_listening(connection, stats, listener, tunnel_id, target_type, socks_timeout).await;
                 });
             }
         }
@@ -49,9 +56,11 @@ pub async fn handle_start_remote_tunnels_stream(
 
 pub async fn handle_remote_tunnel_listening(
     connection: Rc<Connection>,
+    stats: Rc<SessionStats>,
     listener: TcpListener,
     tunnel_id: RemoteTunnelID,
     target_type: TunnelTargetType,
+    socks_timeout: Option<Duration>,
 ) {
     loop {
         let (tcp_stream, _from) = match listener.accept().await {
@@ -63,8 +72,9 @@ pub async fn handle_remote_tunnel_listening(
         };
 
         let connection = Rc::clone(&connection);
+        let stats = Rc::clone(&stats);
         tokio::task::spawn_local(async move {
-            match handle_remote_tunnel(connection, tcp_stream, tunnel_id, target_type).await {
+            match handle_remote_tunnel(connection, &stats, tcp_stream, tunnel_id, target_type, socks_timeout).await {
                 Ok(()) => {}
                 Err(error) => println!("Remote tunnel task finished with error: {error}"),
             }
@@ -74,9 +84,11 @@ pub async fn handle_remote_tunnel_listening(
 
 pub async fn handle_remote_tunnel(
     connection: Rc<Connection>,
+    stats: &SessionStats,
     mut tcp_stream: TcpStream,
     tunnel_id: RemoteTunnelID,
     target_type: TunnelTargetType,
+    socks_timeout: Option<Duration>,
 ) -> io::Result<()> {
     let (mut read_half, mut write_half) = tcp_stream.split();
 
@@ -86,7 +98,7 @@ pub async fn handle_remote_tunnel(
             None
         }
         TunnelTargetType::Socks => {
-            let request_result = socks::read_request(&mut read_half, &mut write_half).await;
+            let request_result = socks::read_request(&mut read_half, &mut write_half, socks_timeout).await;
 
             if let Err(socks_error) = &request_result {
                 println!("Socks error: {socks_error}");
@@ -97,6 +109,8 @@ pub async fn handle_remote_tunnel(
         }
     };
 
+    // Declared before the stream halves so it's dropped after them, keeping the stream counted until it's closed.
+    let _stream_guard;
     let (mut send_stream, mut recv_stream) = match connection.open_bi().await {
         Ok(t) => t,
         Err(error) => {
@@ -104,6 +118,7 @@ pub async fn handle_remote_tunnel(
             return Err(error.into());
         }
     };
+    _stream_guard = stats.track_stream();
 
     let maybe_target = maybe_socks_data.as_ref().map(|(_, addr)| addr.as_ref());
     let request = OpenRemoteConnectionRequestRef::new(tunnel_id, maybe_target);
@@ -120,9 +135,11 @@ pub async fn handle_remote_tunnel(
 
     let bound_address = response.result.map_err(|(_, error)| error)?;
     println!("Remote tunnel connected (remote socket bound at {bound_address})");
+    let tunnel_index = Some(tunnel_id.0 as usize);
+    stats.record_connection(tunnel_index);
     let result = try_join!(
-        tokio::io::copy(&mut read_half, &mut send_stream),
-        tokio::io::copy(&mut recv_stream, &mut write_half),
+        copy_counted(&mut read_half, &mut send_stream, |bytes| stats.record_sent(tunnel_index, bytes)),
+        copy_counted(&mut recv_stream, &mut write_half, |bytes| stats.record_received(tunnel_index, bytes)),
     );
 
     match result {
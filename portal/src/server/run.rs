@@ -1,15 +1,76 @@
-use std::{io, net::SocketAddr, rc::Rc};
+use std::{
+    cell::Cell,
+    io,
+    net::SocketAddr,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 use portal_tunneler_proto::{serialize::ByteRead, shared::ClientStreamRequest};
 use quinn::{Connecting, Connection, Endpoint, RecvStream, SendStream, VarInt};
-use tokio::{
-    select,
-    task::{AbortHandle, JoinHandle},
+use tokio::{select, sync::Notify};
+
+use crate::{
+    puncher::BackgroundTaskHandle,
+    utils::{SessionStats, SessionSummary},
 };
 
 use super::{local_tunnels::handle_open_local_tunnel_stream, remote_tunnels::handle_start_remote_tunnels_stream};
 
-pub async fn run_server(endpoint: Endpoint, abort_on_connect: Option<JoinHandle<()>>, address_filter: Option<SocketAddr>) {
+/// A deadline for accepting a connection, shared by all of the server's endpoints so a connection
+/// established on any of them stops the timeout for all of them.
+pub struct AcceptTimeout {
+    deadline: Instant,
+    connected: Cell<bool>,
+    notify: Notify,
+}
+
+impl AcceptTimeout {
+    /// Creates a new [`AcceptTimeout`] which expires after the specified duration from now.
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + timeout,
+            connected: Cell::new(false),
+            notify: Notify::new(),
+        }
+    }
+
+    /// Marks that a connection was established, so the timeout no longer applies.
+    pub fn set_connected(&self) {
+        self.connected.set(true);
+        self.notify.notify_waiters();
+    }
+
+    /// Waits until either the deadline passes, returning `true`, or a connection is established,
+    /// returning `false`.
+    pub async fn wait(&self) -> bool {
+        if self.connected.get() {
+            return false;
+        }
+
+        select! {
+            _ = self.notify.notified() => false,
+            _ = tokio::time::sleep_until(tokio::time::Instant::from_std(self.deadline)) => !self.connected.get(),
+        }
+    }
+}
+
+/// Waits on the accept timeout if `Some` (see [`AcceptTimeout::wait`]), or never finishes if `None`.
+async fn wait_accept_timeout(accept_timeout: Option<&AcceptTimeout>) -> bool {
+    match accept_timeout {
+        Some(accept_timeout) => accept_timeout.wait().await,
+        None => std::future::pending().await,
+    }
+}
+
+pub async fn run_server(
+    endpoint: Endpoint,
+    stop_on_connect: Option<BackgroundTaskHandle>,
+    address_filter: Option<SocketAddr>,
+    summary: bool,
+    mut accept_timeout: Option<Rc<AcceptTimeout>>,
+    socks_timeout: Option<Duration>,
+) {
     println!("Starting server on {}", endpoint.local_addr().unwrap());
 
     loop {
@@ -17,6 +78,16 @@ pub async fn run_server(endpoint: Endpoint, abort_on_connect: Option<JoinHandle<
         let incoming_connection = select! {
             biased;
             v = endpoint.accept() => v,
+            expired = wait_accept_timeout(accept_timeout.as_deref()) => {
+                if expired {
+                    println!("No connection was accepted before the accept timeout");
+                    break;
+                }
+
+                // A connection was established, so the accept timeout no longer applies.
+                accept_timeout = None;
+                continue;
+            }
             //_ = tokio::signal::ctrl_c() => break, // TODO: Find out why Ctrl-C hangs instead of closing
         };
 
@@ -26,10 +97,11 @@ pub async fn run_server(endpoint: Endpoint, abort_on_connect: Option<JoinHandle<
             None => break,
         };
 
-        let abort_on_connect = abort_on_connect.as_ref().map(|h| h.abort_handle());
+        let stop_on_connect = stop_on_connect.clone();
+        let accept_timeout = accept_timeout.clone();
         println!("Incoming connection from addr={}", incoming_connection.remote_address());
         tokio::task::spawn_local(async move {
-            handle_connection(incoming_connection, abort_on_connect).await;
+            handle_connection(incoming_connection, stop_on_connect, accept_timeout, summary, socks_timeout).await;
         });
     }
 
@@ -37,17 +109,28 @@ pub async fn run_server(endpoint: Endpoint, abort_on_connect: Option<JoinHandle<
     println!("Server closed");
 }
 
-async fn handle_connection(incoming_connection: Connecting, abort_on_connect: Option<AbortHandle>) {
+/// Handles an incoming connection until it closes. Returns a summary of the session, or `None` if
+/// the connection couldn't be established.
+pub async fn handle_connection(
+    incoming_connection: Connecting,
+    stop_on_connect: Option<BackgroundTaskHandle>,
+    accept_timeout: Option<Rc<AcceptTimeout>>,
+    summary: bool,
+    socks_timeout: Option<Duration>,
+) -> Option<SessionSummary> {
     let connection = match incoming_connection.await {
         Ok(c) => c,
         Err(connection_error) => {
             println!("Failed to accept incoming connection: {connection_error}");
-            return;
+            return None;
         }
     };
 
-    abort_on_connect.inspect(|h| h.abort());
+    stop_on_connect.inspect(|h| h.stop());
+    accept_timeout.inspect(|t| t.set_connected());
+
     let connection = Rc::new(connection);
+    let stats = Rc::new(SessionStats::with_tunnel_label("Remote tunnel ID"));
 
     loop {
         let (send_stream, recv_stream) = match connection.accept_bi().await {
@@ -58,21 +141,156 @@ async fn handle_connection(incoming_connection: Connecting, abort_on_connect: Op
             }
         };
 
-        println!("Accepted bidirectional stream {} {}", send_stream.id(), recv_stream.id());
         let connection = Rc::clone(&connection);
+        let stats = Rc::clone(&stats);
         tokio::task::spawn_local(async move {
-            match handle_incoming_bi_stream(connection, send_stream, recv_stream).await {
+            let _stream_guard = stats.track_stream();
+            println!(
+                "Accepted bidirectional stream {} {} ({} streams open)",
+                send_stream.id(),
+                recv_stream.id(),
+                stats.open_streams()
+            );
+            match handle_incoming_bi_stream(connection, Rc::clone(&stats), send_stream, recv_stream, socks_timeout).await {
                 Ok(()) => {}
                 Err(error) => println!("Handle bidi stream finished with error: {error}"),
             }
         });
     }
+
+    let session_summary = stats.summary();
+    if summary {
+        println!("{session_summary}");
+    }
+
+    Some(session_summary)
 }
 
-async fn handle_incoming_bi_stream(connection: Rc<Connection>, send_stream: SendStream, mut recv_stream: RecvStream) -> io::Result<()> {
+async fn handle_incoming_bi_stream(
+    connection: Rc<Connection>,
+    stats: Rc<SessionStats>,
+    send_stream: SendStream,
+    mut recv_stream: RecvStream,
+    socks_timeout: Option<Duration>,
+) -> io::Result<()> {
     let request = ClientStreamRequest::read(&mut recv_stream).await?;
     match request {
-        ClientStreamRequest::OpenLocalTunnelConnection => handle_open_local_tunnel_stream(send_stream, recv_stream).await,
-        ClientStreamRequest::StartRemoteTunnels => handle_start_remote_tunnels_stream(connection, send_stream, recv_stream).await,
+        ClientStreamRequest::OpenLocalTunnelConnection => handle_open_local_tunnel_stream(&stats, send_stream, recv_stream).await,
+        ClientStreamRequest::StartRemoteTunnels => {
+            handle_start_remote_tunnels_stream(connection, stats, send_stream, recv_stream, socks_timeout).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{Ipv4Addr, SocketAddr, UdpSocket},
+        rc::Rc,
+        time::{Duration, Instant},
+    };
+
+    use quinn::Endpoint;
+    use tokio::task::LocalSet;
+
+    use crate::endpoint::{make_endpoint, EndpointSocketSource};
+
+    use super::{run_server, AcceptTimeout};
+
+    const ACCEPT_TIMEOUT: Duration = Duration::from_millis(300);
+
+    fn make_local_endpoint(is_client: bool, is_server: bool) -> Endpoint {
+        let socket = UdpSocket::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0)).unwrap();
+        make_endpoint(EndpointSocketSource::Simple(socket), is_client, is_server).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_accept_timeout_without_connections() {
+        let server = make_local_endpoint(false, true);
+
+        let start = Instant::now();
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            run_server(server.clone(), None, None, false, Some(Rc::new(AcceptTimeout::new(ACCEPT_TIMEOUT))), None),
+        )
+        .await;
+
+        assert!(result.is_ok(), "The server should stop after the accept timeout");
+        assert!(start.elapsed() >= ACCEPT_TIMEOUT);
+        assert!(server.accept().await.is_none(), "The endpoint should be closed");
+    }
+
+    #[tokio::test]
+    async fn test_accept_timeout_after_connecting() {
+        let server = make_local_endpoint(false, true);
+        let server_address = server.local_addr().unwrap();
+        let client = make_local_endpoint(true, false);
+
+        LocalSet::new()
+            .run_until(async move {
+                let accept_timeout = Some(Rc::new(AcceptTimeout::new(ACCEPT_TIMEOUT)));
+                let server_task = tokio::task::spawn_local(run_server(server, None, None, false, accept_timeout, None));
+
+                let connection = client
+                    .connect(server_address, &server_address.ip().to_string())
+                    .unwrap()
+                    .await
+                    .unwrap();
+                tokio::time::sleep(ACCEPT_TIMEOUT * 2).await;
+
+                assert!(
+                    !server_task.is_finished(),
+                    "The server shouldn't stop once a connection is established"
+                );
+                assert!(connection.close_reason().is_none());
+                server_task.abort();
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_accept_timeout_shared_by_endpoints() {
+        let connected_server = make_local_endpoint(false, true);
+        let connected_address = connected_server.local_addr().unwrap();
+        let idle_server = make_local_endpoint(false, true);
+        let client = make_local_endpoint(true, false);
+
+        LocalSet::new()
+            .run_until(async move {
+                let accept_timeout = Rc::new(AcceptTimeout::new(ACCEPT_TIMEOUT));
+                let connected_task = tokio::task::spawn_local(run_server(
+                    connected_server,
+                    None,
+                    None,
+                    false,
+                    Some(Rc::clone(&accept_timeout)),
+                    None,
+                ));
+                let idle_task = tokio::task::spawn_local(run_server(
+                    idle_server.clone(),
+                    None,
+                    None,
+                    false,
+                    Some(accept_timeout),
+                    None,
+                ));
+
+                let connection = client
+                    .connect(connected_address, &connected_address.ip().to_string())
+                    .unwrap()
+                    .await
+                    .unwrap();
+                tokio::time::sleep(ACCEPT_TIMEOUT * 2).await;
+
+                assert!(
+                    !idle_task.is_finished(),
+                    "A connection on one endpoint should stop the accept timeout on the others"
+                );
+                assert!(!connected_task.is_finished());
+                assert!(connection.close_reason().is_none());
+                connected_task.abort();
+                idle_task.abort();
+            })
+            .await;
     }
 }
@@ -1,6 +1,7 @@
 use std::{
     io::{Error, ErrorKind},
     net::SocketAddr,
+    time::Duration,
 };
 
 use portal_tunneler_proto::{
@@ -24,6 +25,7 @@ pub enum SocksRequestError {
     Socks5InvalidVersion(u8),
     Socks5InvalidCommand(u8),
     Socks5InvalidAtyp(u8),
+    Timeout,
 }
 
 impl std::fmt::Display for SocksRequestError {
@@ -36,6 +38,7 @@ impl std::fmt::Display for SocksRequestError {
             Self::Socks5InvalidVersion(ver) => write!(f, "Client requested SOCKS5, but then specified another version: {ver}"),
             Self::Socks5InvalidCommand(cmd) => write!(f, "Client requested invalid SOCKS5 command: {cmd}"),
             Self::Socks5InvalidAtyp(atyp) => write!(f, "Client requested invalid SOCKS5 address type: {atyp}"),
+            Self::Timeout => write!(f, "Client didn't complete the SOCKS request in time"),
         }
     }
 }
@@ -44,6 +47,7 @@ impl From<SocksRequestError> for Error {
     fn from(value: SocksRequestError) -> Self {
         match value {
             SocksRequestError::IO(error) => error,
+            SocksRequestError::Timeout => Error::new(ErrorKind::TimedOut, format!("{value}")),
             other => Error::new(ErrorKind::Other, format!("{other}")),
         }
     }
@@ -55,7 +59,26 @@ impl From<Error> for SocksRequestError {
     }
 }
 
-pub async fn read_request<R, W>(reader: &mut R, writer: &mut W) -> Result<(SocksVersion, AddressOrDomainname), SocksRequestError>
+/// Reads a SOCKS request from a client, negotiating with it as needed. If `timeout` is `Some` and
+/// the request isn't completed within it, fails with [`SocksRequestError::Timeout`].
+pub async fn read_request<R, W>(
+    reader: &mut R,
+    writer: &mut W,
+    timeout: Option<Duration>,
+) -> Result<(SocksVersion, AddressOrDomainname), SocksRequestError>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+{
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, read_request_inner(reader, writer))
+            .await
+            .unwrap_or(Err(SocksRequestError::Timeout)),
+        None => read_request_inner(reader, writer).await,
+    }
+}
+
+async fn read_request_inner<R, W>(reader: &mut R, writer: &mut W) -> Result<(SocksVersion, AddressOrDomainname), SocksRequestError>
 where
     R: AsyncRead + Unpin + ?Sized,
     W: AsyncWrite + Unpin + ?Sized,
@@ -98,3 +121,38 @@ where
         SocksVersion::Five => socks5::send_response(writer, result).await,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use tokio::io::AsyncWriteExt;
+
+    use super::{read_request, SocksRequestError};
+
+    #[tokio::test]
+    async fn test_read_request_timeout() {
+        let (client, server) = tokio::io::duplex(64);
+        let (mut read_half, mut write_half) = tokio::io::split(server);
+
+        let start = Instant::now();
+        let task = read_request(&mut read_half, &mut write_half, Some(Duration::from_millis(200)));
+        let result = tokio::time::timeout(Duration::from_secs(5), task).await;
+
+        assert!(matches!(result, Ok(Err(SocksRequestError::Timeout))));
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        drop(client);
+    }
+
+    #[tokio::test]
+    async fn test_read_request_within_timeout() {
+        let (mut client, server) = tokio::io::duplex(64);
+        let (mut read_half, mut write_half) = tokio::io::split(server);
+
+        // A SOCKS4 CONNECT request to 127.0.0.1:80 with an empty user ID.
+        client.write_all(&[4, 1, 0, 80, 127, 0, 0, 1, 0]).await.unwrap();
+
+        let result = read_request(&mut read_half, &mut write_half, Some(Duration::from_secs(5))).await;
+        assert!(result.is_ok(), "Expected a successful request, got {result:?}");
+    }
+}
@@ -1,9 +1,11 @@
 mod macros;
 mod sockets;
+mod stats;
 mod strings;
 mod time;
 
 pub use macros::*;
 pub use sockets::*;
+pub use stats::*;
 pub use strings::*;
 pub use time::*;
@@ -8,7 +8,10 @@ use std::{
 
 use inlined::{CompactVec, InlineString};
 use portal_tunneler_proto::shared::AddressOrDomainnameRef;
-use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream, UdpSocket},
+};
 
 /// An empty IPv4 [`SocketAddr`] with port 0
 pub const UNSPECIFIED_SOCKADDR_V4: SocketAddr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
@@ -112,3 +115,35 @@ pub async fn bind_connect(address: AddressOrDomainnameRef<'_>) -> io::Result<Tcp
         }
     }
 }
+
+/// The size of the buffer used by [`copy_counted`], the same as [`tokio::io::copy`]'s.
+const COPY_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Copies all the data from `reader` into `writer` like [`tokio::io::copy`], returning the amount
+/// of bytes copied.
+///
+/// `on_copied` is called with the amount of bytes every time a chunk is written, so the bytes are
+/// accounted for even if the copy later fails.
+pub async fn copy_counted<R, W, F>(reader: &mut R, writer: &mut W, mut on_copied: F) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    W: AsyncWrite + Unpin + ?Sized,
+    F: FnMut(u64),
+{
+    let mut buf = vec![0; COPY_BUFFER_SIZE];
+    let mut copied = 0;
+
+    loop {
+        let len = reader.read(&mut buf).await?;
+        if len == 0 {
+            break;
+        }
+
+        writer.write_all(&buf[..len]).await?;
+        copied += len as u64;
+        on_copied(len as u64);
+    }
+
+    writer.flush().await?;
+    Ok(copied)
+}
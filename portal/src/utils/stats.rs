@@ -0,0 +1,261 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Counters for the connections that went through a tunnel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TunnelStats {
+    /// The amount of tunneled connections, including the ones still open and the ones that ended
+    /// with an error.
+    pub connections: u64,
+
+    /// The total amount of bytes sent to the remote peer.
+    pub bytes_sent: u64,
+
+    /// The total amount of bytes received from the remote peer.
+    pub bytes_received: u64,
+}
+
+/// Keeps track of the tunneled connections over a session, for printing a [`SessionSummary`] when
+/// the session ends.
+///
+/// On the client, tunnels are identified by their [`TunnelSpec::index`]. The server doesn't know
+/// those indices, so it identifies remote tunnels by their [`RemoteTunnelID`] instead, while local
+/// tunnel connections carry no tunnel identification and are recorded without a tunnel. Such
+/// connections only count towards the totals.
+///
+/// This also keeps track of how many streams are concurrently open on the connection, which helps
+/// detect when the connection's stream limit is throttling the tunnels.
+///
+/// [`TunnelSpec::index`]: portal_tunneler_proto::shared::TunnelSpec::index
+/// [`RemoteTunnelID`]: portal_tunneler_proto::shared::RemoteTunnelID
+pub struct SessionStats {
+    started: Instant,
+    tunnel_label: &'static str,
+    inner: RefCell<SessionStatsInner>,
+}
+
+struct SessionStatsInner {
+    totals: TunnelStats,
+    tunnels: BTreeMap<usize, TunnelStats>,
+    open_streams: u64,
+    max_concurrent_streams: u64,
+}
+
+impl SessionStats {
+    /// Creates a new [`SessionStats`] whose tunnels are listed in the summary as "Tunnel {index}".
+    pub fn new() -> Self {
+        Self::with_tunnel_label("Tunnel")
+    }
+
+    /// Creates a new [`SessionStats`] whose tunnels are listed in the summary with the specified
+    /// label followed by the tunnel's identifier.
+    pub fn with_tunnel_label(tunnel_label: &'static str) -> Self {
+        Self {
+            started: Instant::now(),
+            tunnel_label,
+            inner: RefCell::new(SessionStatsInner {
+                totals: TunnelStats::default(),
+                tunnels: BTreeMap::new(),
+                open_streams: 0,
+                max_concurrent_streams: 0,
+            }),
+        }
+    }
+
+    fn update<F: Fn(&mut TunnelStats)>(&self, tunnel_index: Option<usize>, f: F) {
+        let mut inner = self.inner.borrow_mut();
+        f(&mut inner.totals);
+        if let Some(index) = tunnel_index {
+            f(inner.tunnels.entry(index).or_default());
+        }
+    }
+
+    /// Records a newly established tunneled connection. Its bytes are recorded separately as they
+    /// are copied, so a connection that ends with an error still counts, along with whatever it
+    /// moved before that.
+    pub fn record_connection(&self, tunnel_index: Option<usize>) {
+        self.update(tunnel_index, |stats| stats.connections += 1);
+    }
+
+    /// Records bytes sent to the remote peer by a tunneled connection.
+    pub fn record_sent(&self, tunnel_index: Option<usize>, bytes: u64) {
+        self.update(tunnel_index, |stats| stats.bytes_sent += bytes);
+    }
+
+    /// Records bytes received from the remote peer by a tunneled connection.
+    pub fn record_received(&self, tunnel_index: Option<usize>, bytes: u64) {
+        self.update(tunnel_index, |stats| stats.bytes_received += bytes);
+    }
+
+    /// Records a newly opened or accepted stream, which is considered open until the returned
+    /// [`StreamGuard`] is dropped.
+    pub fn track_stream(&self) -> StreamGuard<'_> {
+        let mut inner = self.inner.borrow_mut();
+        inner.open_streams += 1;
+        inner.max_concurrent_streams = inner.max_concurrent_streams.max(inner.open_streams);
+        StreamGuard { stats: self }
+    }
+
+    /// Gets the amount of currently open streams.
+    pub fn open_streams(&self) -> u64 {
+        self.inner.borrow().open_streams
+    }
+
+    /// Gets a summary of the session so far.
+    pub fn summary(&self) -> SessionSummary {
+        let inner = self.inner.borrow();
+        SessionSummary {
+            duration: self.started.elapsed(),
+            tunnel_label: self.tunnel_label,
+            totals: inner.totals,
+            tunnels: inner.tunnels.iter().map(|(index, stats)| (*index, *stats)).collect(),
+            max_concurrent_streams: inner.max_concurrent_streams,
+        }
+    }
+}
+
+/// Marks a stream as open in a [`SessionStats`] for as long as it lives.
+pub struct StreamGuard<'a> {
+    stats: &'a SessionStats,
+}
+
+impl Drop for StreamGuard<'_> {
+    fn drop(&mut self) {
+        self.stats.inner.borrow_mut().open_streams -= 1;
+    }
+}
+
+/// A snapshot of a [`SessionStats`], which can be printed as an end-of-session report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionSummary {
+    pub duration: Duration,
+    pub tunnel_label: &'static str,
+    pub totals: TunnelStats,
+    pub tunnels: Vec<(usize, TunnelStats)>,
+    pub max_concurrent_streams: u64,
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Session summary:")?;
+        writeln!(f, "  Duration: {}s", self.duration.as_secs())?;
+        writeln!(f, "  Connections handled: {}", self.totals.connections)?;
+        writeln!(f, "  Max concurrent streams: {}", self.max_concurrent_streams)?;
+        write!(
+            f,
+            "  Bytes sent: {}, bytes received: {}",
+            self.totals.bytes_sent, self.totals.bytes_received
+        )?;
+
+        for (index, stats) in &self.tunnels {
+            write!(
+                f,
+                "\n  {} {index}: {} connections, {} bytes sent, {} bytes received",
+                self.tunnel_label, stats.connections, stats.bytes_sent, stats.bytes_received
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{SessionStats, TunnelStats};
+
+    #[test]
+    fn test_session_summary() {
+        let stats = SessionStats::new();
+        for (tunnel_index, sent, received) in [(Some(0), 100, 2000), (Some(1), 5, 7), (Some(0), 50, 1000), (None, 1, 1)] {
+            stats.record_connection(tunnel_index);
+            stats.record_sent(tunnel_index, sent);
+            stats.record_received(tunnel_index, received);
+        }
+
+        let mut summary = stats.summary();
+        assert_eq!(
+            summary.totals,
+            TunnelStats {
+                connections: 4,
+                bytes_sent: 156,
+                bytes_received: 3008,
+            }
+        );
+        assert_eq!(summary.tunnels.len(), 2);
+
+        summary.duration = Duration::from_secs(42);
+        let s = summary.to_string();
+        assert!(s.contains("Duration: 42s"));
+        assert!(s.contains("Connections handled: 4"));
+        assert!(s.contains("Bytes sent: 156, bytes received: 3008"));
+        assert!(s.contains("Tunnel 0: 2 connections, 150 bytes sent, 3000 bytes received"));
+        assert!(s.contains("Tunnel 1: 1 connections, 5 bytes sent, 7 bytes received"));
+    }
+
+    #[test]
+    fn test_session_summary_tunnel_label() {
+        let stats = SessionStats::with_tunnel_label("Remote tunnel ID");
+        stats.record_connection(Some(3));
+        stats.record_sent(Some(3), 10);
+        stats.record_received(Some(3), 20);
+
+        let s = stats.summary().to_string();
+        assert!(s.contains("Remote tunnel ID 3: 1 connections, 10 bytes sent, 20 bytes received"));
+        assert!(!s.contains("  Tunnel 3"));
+    }
+
+    #[test]
+    fn test_unfinished_connection() {
+        let stats = SessionStats::new();
+        stats.record_connection(Some(0));
+        stats.record_sent(Some(0), 10);
+
+        let expected = TunnelStats {
+            connections: 1,
+            bytes_sent: 10,
+            bytes_received: 0,
+        };
+        assert_eq!(stats.summary().totals, expected);
+        assert_eq!(stats.summary().tunnels, [(0, expected)]);
+
+        stats.record_received(Some(0), 3);
+        stats.record_sent(Some(0), 5);
+        assert_eq!(stats.summary().totals.bytes_sent, 15);
+        assert_eq!(stats.summary().totals.bytes_received, 3);
+        assert_eq!(stats.summary().totals.connections, 1);
+    }
+
+    #[test]
+    fn test_empty_session_summary() {
+        let summary = SessionStats::new().summary();
+        assert_eq!(summary.totals, TunnelStats::default());
+        assert!(summary.tunnels.is_empty());
+        assert!(summary.to_string().contains("Connections handled: 0"));
+    }
+
+    #[test]
+    fn test_max_concurrent_streams() {
+        let stats = SessionStats::new();
+
+        let streams: Vec<_> = (0..5).map(|_| stats.track_stream()).collect();
+        assert_eq!(stats.open_streams(), 5);
+        assert_eq!(stats.summary().max_concurrent_streams, 5);
+
+        drop(streams);
+        assert_eq!(stats.open_streams(), 0);
+
+        let _first = stats.track_stream();
+        let _second = stats.track_stream();
+        assert_eq!(stats.open_streams(), 2);
+
+        let summary = stats.summary();
+        assert_eq!(summary.max_concurrent_streams, 5);
+        assert!(summary.to_string().contains("Max concurrent streams: 5"));
+    }
+}